@@ -19,6 +19,14 @@
 const TS_BYTES = 8;
 const UID_BYTES = 16;
 const BOOL_BYTES = 1;
+/**
+ * ## Variable size
+ *
+ * Size marker for components whose size depends on their content.
+ * Variable-size components are encoded with a 4-byte (big-endian) length prefix.
+ */
+const VAR_BYTES = -1;
+const LEN_BYTES = 4;
 /**
  * ## Client binary message object
  *
@@ -43,6 +51,38 @@ const clientBinaryMessageObj = {
             { name: "register", size: TS_BYTES + BOOL_BYTES },
         ],
     },
+    "sequence-op": {
+        discriminant: 2,
+        components: [
+            { name: "ts", size: TS_BYTES },
+            { name: "id", size: UID_BYTES },
+            { name: "op", size: VAR_BYTES },
+        ],
+    },
+    "rich-text-op": {
+        discriminant: 3,
+        components: [
+            { name: "ts", size: TS_BYTES },
+            { name: "id", size: UID_BYTES },
+            { name: "op", size: VAR_BYTES },
+        ],
+    },
+    register: {
+        discriminant: 4,
+        components: [
+            { name: "ts", size: TS_BYTES },
+            { name: "id", size: UID_BYTES },
+            { name: "register", size: VAR_BYTES },
+        ],
+    },
+    "record-patch": {
+        discriminant: 5,
+        components: [
+            { name: "ts", size: TS_BYTES },
+            { name: "id", size: UID_BYTES },
+            { name: "patch", size: VAR_BYTES },
+        ],
+    },
 };
 /**
  * ## Server binary message object
@@ -65,6 +105,42 @@ const serverBinaryMessageObj = {
             { name: "register", size: TS_BYTES + BOOL_BYTES },
         ],
     },
+    "sequence-op": {
+        discriminant: 2,
+        components: [
+            { name: "nid", size: UID_BYTES },
+            { name: "ts", size: TS_BYTES },
+            { name: "id", size: UID_BYTES },
+            { name: "op", size: VAR_BYTES },
+        ],
+    },
+    "rich-text-op": {
+        discriminant: 3,
+        components: [
+            { name: "nid", size: UID_BYTES },
+            { name: "ts", size: TS_BYTES },
+            { name: "id", size: UID_BYTES },
+            { name: "op", size: VAR_BYTES },
+        ],
+    },
+    register: {
+        discriminant: 4,
+        components: [
+            { name: "nid", size: UID_BYTES },
+            { name: "ts", size: TS_BYTES },
+            { name: "id", size: UID_BYTES },
+            { name: "register", size: VAR_BYTES },
+        ],
+    },
+    "record-patch": {
+        discriminant: 5,
+        components: [
+            { name: "nid", size: UID_BYTES },
+            { name: "ts", size: TS_BYTES },
+            { name: "id", size: UID_BYTES },
+            { name: "patch", size: VAR_BYTES },
+        ],
+    },
 };
 //#endregion
 //#region Binary message construction
@@ -79,13 +155,21 @@ const serverBinaryMessageObj = {
  */
 function buildBinaryMessage(binaryMessageObj, message) {
     const messageObj = binaryMessageObj[message.msgCode];
-    const layout = messageObj.components.map(c => c.size);
+    const values = messageObj.components.map(c => message.components[c.name]);
+    const layout = messageObj.components.map((c, i) => c.size === VAR_BYTES ? LEN_BYTES + values[i].length : c.size);
     const messageSize = layout.reduce((prev, curr) => prev + curr, 1);
     const binMessage = new Uint8Array(messageSize);
     binMessage.set(new Uint8Array([messageObj.discriminant]), 0);
     let currPosition = 1;
     messageObj.components.forEach((component, i) => {
-        binMessage.set(message.components[component.name], currPosition);
+        if (component.size === VAR_BYTES) {
+            // Variable-size components are preceded by their length.
+            new DataView(binMessage.buffer).setUint32(currPosition, values[i].length);
+            binMessage.set(values[i], currPosition + LEN_BYTES);
+        }
+        else {
+            binMessage.set(values[i], currPosition);
+        }
         currPosition += layout[i];
     });
     return binMessage;
@@ -173,8 +257,16 @@ function parseBinaryMessage(binaryMessageObj, binMessage) {
     let currPosition = 1;
     const components = {};
     for (const { name, size } of val.components) {
-        components[name] = binMessage.slice(currPosition, currPosition + size);
-        currPosition += size;
+        if (size === VAR_BYTES) {
+            const length = new DataView(binMessage.buffer, binMessage.byteOffset).getUint32(currPosition);
+            currPosition += LEN_BYTES;
+            components[name] = binMessage.slice(currPosition, currPosition + length);
+            currPosition += length;
+        }
+        else {
+            components[name] = binMessage.slice(currPosition, currPosition + size);
+            currPosition += size;
+        }
     }
     return [
         { msgCode, components },
@@ -164,6 +164,15 @@ const TS_BYTES = 8 as 8;
 const UID_BYTES = 16 as 16;
 const BOOL_BYTES = 1 as 1;
 
+/**
+ * ## Variable size
+ *
+ * Size marker for components whose size depends on their content.
+ * Variable-size components are encoded with a 4-byte (big-endian) length prefix.
+ */
+const VAR_BYTES = -1 as -1;
+const LEN_BYTES = 4;
+
 /**
  * ## Client binary message object
  *
@@ -188,6 +197,38 @@ const clientBinaryMessageObj = {
       { name: "register" as "register", size: TS_BYTES + BOOL_BYTES },
     ],
   },
+  "sequence-op": {
+    discriminant: 2 as 2,
+    components: [
+      { name: "ts" as "ts", size: TS_BYTES },
+      { name: "id" as "id", size: UID_BYTES },
+      { name: "op" as "op", size: VAR_BYTES },
+    ],
+  },
+  "rich-text-op": {
+    discriminant: 3 as 3,
+    components: [
+      { name: "ts" as "ts", size: TS_BYTES },
+      { name: "id" as "id", size: UID_BYTES },
+      { name: "op" as "op", size: VAR_BYTES },
+    ],
+  },
+  register: {
+    discriminant: 4 as 4,
+    components: [
+      { name: "ts" as "ts", size: TS_BYTES },
+      { name: "id" as "id", size: UID_BYTES },
+      { name: "register" as "register", size: VAR_BYTES },
+    ],
+  },
+  "record-patch": {
+    discriminant: 5 as 5,
+    components: [
+      { name: "ts" as "ts", size: TS_BYTES },
+      { name: "id" as "id", size: UID_BYTES },
+      { name: "patch" as "patch", size: VAR_BYTES },
+    ],
+  },
 };
 type ClientBinaryMessageObj = typeof clientBinaryMessageObj;
 
@@ -212,6 +253,42 @@ const serverBinaryMessageObj = {
       { name: "register" as "register", size: TS_BYTES + BOOL_BYTES },
     ],
   },
+  "sequence-op": {
+    discriminant: 2 as 2,
+    components: [
+      { name: "nid" as "nid", size: UID_BYTES },
+      { name: "ts" as "ts", size: TS_BYTES },
+      { name: "id" as "id", size: UID_BYTES },
+      { name: "op" as "op", size: VAR_BYTES },
+    ],
+  },
+  "rich-text-op": {
+    discriminant: 3 as 3,
+    components: [
+      { name: "nid" as "nid", size: UID_BYTES },
+      { name: "ts" as "ts", size: TS_BYTES },
+      { name: "id" as "id", size: UID_BYTES },
+      { name: "op" as "op", size: VAR_BYTES },
+    ],
+  },
+  register: {
+    discriminant: 4 as 4,
+    components: [
+      { name: "nid" as "nid", size: UID_BYTES },
+      { name: "ts" as "ts", size: TS_BYTES },
+      { name: "id" as "id", size: UID_BYTES },
+      { name: "register" as "register", size: VAR_BYTES },
+    ],
+  },
+  "record-patch": {
+    discriminant: 5 as 5,
+    components: [
+      { name: "nid" as "nid", size: UID_BYTES },
+      { name: "ts" as "ts", size: TS_BYTES },
+      { name: "id" as "id", size: UID_BYTES },
+      { name: "patch" as "patch", size: VAR_BYTES },
+    ],
+  },
 };
 type ServerBinaryMessageObj = typeof serverBinaryMessageObj;
 //#endregion
@@ -267,7 +344,12 @@ function buildBinaryMessage(
 ): Uint8Array {
   const messageObj = binaryMessageObj[message.msgCode];
 
-  const layout = messageObj.components.map(c => c.size);
+  const values: Uint8Array[] = messageObj.components.map(
+    c => message.components[c.name as keyof typeof message["components"]]
+  );
+  const layout = messageObj.components.map((c, i) =>
+    c.size === VAR_BYTES ? LEN_BYTES + values[i].length : c.size
+  );
   const messageSize = layout.reduce((prev, curr) => prev + curr, 1);
 
   const binMessage = new Uint8Array(messageSize);
@@ -275,10 +357,13 @@ function buildBinaryMessage(
 
   let currPosition = 1;
   messageObj.components.forEach((component, i) => {
-    binMessage.set(
-      message.components[component.name as keyof typeof message["components"]],
-      currPosition
-    );
+    if (component.size === VAR_BYTES) {
+      // Variable-size components are preceded by their length.
+      new DataView(binMessage.buffer).setUint32(currPosition, values[i].length);
+      binMessage.set(values[i], currPosition + LEN_BYTES);
+    } else {
+      binMessage.set(values[i], currPosition);
+    }
     currPosition += layout[i];
   });
   return binMessage;
@@ -394,8 +479,18 @@ function parseBinaryMessage(
   let currPosition = 1;
   const components = {};
   for (const { name, size } of val.components) {
-    components[name] = binMessage.slice(currPosition, currPosition + size);
-    currPosition += size;
+    if (size === VAR_BYTES) {
+      const length = new DataView(
+        binMessage.buffer,
+        binMessage.byteOffset
+      ).getUint32(currPosition);
+      currPosition += LEN_BYTES;
+      components[name] = binMessage.slice(currPosition, currPosition + length);
+      currPosition += length;
+    } else {
+      components[name] = binMessage.slice(currPosition, currPosition + size);
+      currPosition += size;
+    }
   }
 
   return [
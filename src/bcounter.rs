@@ -0,0 +1,349 @@
+//! # BCounter CRDT
+//!
+//! Implementation of a _bounded counter_ CRDT, whose value never goes below zero.
+//!
+//! The counter is an _escrow_ of rights: incrementing the counter by `n` gives the incrementing
+//! node `n` rights, decrementing it by `n` spends `n` rights of the decrementing node. A node can
+//! only decrement as long as it holds enough rights, so the bound holds even if all nodes
+//! decrement concurrently. Nodes that run out of rights can receive some from other nodes through
+//! transfers.
+//!
+//! Increments, decrements and transfers are all grow-only counts, so states merge by taking the
+//! maximum of each count, like the other counters. Every mutator returns a delta which can be sent
+//! to the other replicas instead of the full state.
+//!
+//! Reference: [Extending eventually consistent cloud databases for enforcing numeric invariants - Balegas et. al.](https://doi.org/10.1109/SRDS.2015.32)
+//!
+//! ### Usage
+//!
+//! ```rust
+//! use crdts::bcounter::{BCounter, BCounterError};
+//! use crdts::uid::UID;
+//!
+//! let (nid_a, nid_b) = (UID::new(), UID::new());
+//!
+//! let mut counter_a = BCounter::new();
+//! let increment = counter_a.increment(nid_a, 5);
+//! let transfer = counter_a.transfer(nid_a, nid_b, 2).unwrap();
+//!
+//! let mut counter_b = BCounter::new();
+//! counter_b.merge_delta(&increment);
+//! counter_b.merge_delta(&transfer);
+//! assert_eq!(counter_b.decrement(nid_b, 3), Err(BCounterError::InsufficientRights));
+//! counter_b.decrement(nid_b, 2).unwrap();
+//! assert_eq!(counter_b.get_value(), 3);
+//! ```
+use crate::{
+    gcounter::GCounter,
+    serialization::{DeserializeError, VarDeserialize, VarSerialize},
+    uid::UID,
+};
+use std::cmp::max;
+use std::collections::BTreeMap;
+
+/// ## Bounded Counter
+///
+/// Representation of a counter whose value is bounded below by zero.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BCounter {
+    /// ### Increments
+    ///
+    /// Amount each node has added to the counter.
+    increments: GCounter,
+
+    /// ### Decrements
+    ///
+    /// Amount each node has subtracted from the counter.
+    decrements: GCounter,
+
+    /// ### Transfers
+    ///
+    /// Amount of rights each node has transferred to each other node, keyed by (source, target).
+    transfers: BTreeMap<(UID, UID), u64>,
+}
+
+impl Default for BCounter {
+    fn default() -> Self {
+        Self {
+            increments: GCounter::new(None),
+            decrements: GCounter::new(None),
+            transfers: BTreeMap::new(),
+        }
+    }
+}
+
+impl BCounter {
+    /// ### New BCounter
+    ///
+    /// Creates a new counter with value 0.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ### Get BCounter Value
+    ///
+    /// Returns the total count among all nodes.
+    pub fn get_value(&self) -> u64 {
+        self.increments
+            .get_value()
+            .saturating_sub(self.decrements.get_value())
+    }
+
+    /// ### Get rights
+    ///
+    /// Returns the amount a node may still decrement (or transfer).
+    pub fn get_rights(&self, node_id: UID) -> u64 {
+        let (received, sent) = self.transfers.iter().fold(
+            (0, 0),
+            |(received, sent): (u64, u64), ((source, target), amount)| {
+                if *target == node_id {
+                    (received.saturating_add(*amount), sent)
+                } else if *source == node_id {
+                    (received, sent.saturating_add(*amount))
+                } else {
+                    (received, sent)
+                }
+            },
+        );
+        Self::count(&self.increments, node_id)
+            .saturating_add(received)
+            .saturating_sub(sent.saturating_add(Self::count(&self.decrements, node_id)))
+    }
+
+    /// ### Increment Counter
+    ///
+    /// Increments the counter, granting the node as many rights.
+    ///
+    /// * `node_id` - ID of the current node.
+    /// * `amount` - Amount to add.
+    /// * Returns the delta of the operation.
+    pub fn increment(&mut self, node_id: UID, amount: u64) -> Self {
+        Self {
+            increments: self.increments.increment_by(node_id, amount),
+            ..Self::default()
+        }
+    }
+
+    /// ### Decrement Counter
+    ///
+    /// Decrements the counter, spending rights of the node.
+    ///
+    /// * `node_id` - ID of the current node.
+    /// * `amount` - Amount to subtract.
+    /// * Returns the delta of the operation.
+    /// * Throws [`BCounterError::InsufficientRights`] if the node holds fewer rights than
+    ///   `amount`. The counter is left unchanged.
+    pub fn decrement(&mut self, node_id: UID, amount: u64) -> Result<Self, BCounterError> {
+        if self.get_rights(node_id) < amount {
+            return Err(BCounterError::InsufficientRights);
+        }
+        Ok(Self {
+            decrements: self.decrements.increment_by(node_id, amount),
+            ..Self::default()
+        })
+    }
+
+    /// ### Transfer rights
+    ///
+    /// Transfers rights from the current node to another one.
+    ///
+    /// * `from` - ID of the current node.
+    /// * `to` - ID of the node receiving the rights.
+    /// * `amount` - Amount of rights to transfer.
+    /// * Returns the delta of the operation.
+    /// * Throws [`BCounterError::InsufficientRights`] if the current node holds fewer rights than
+    ///   `amount`. The counter is left unchanged.
+    /// * Throws [`BCounterError::Overflow`] if the total transferred from `from` to `to` would
+    ///   not fit in a [`u64`]. The counter is left unchanged.
+    pub fn transfer(&mut self, from: UID, to: UID, amount: u64) -> Result<Self, BCounterError> {
+        if self.get_rights(from) < amount {
+            return Err(BCounterError::InsufficientRights);
+        }
+        let mut delta = Self::default();
+        if from != to {
+            let transferred = self.transfers.entry((from, to)).or_insert(0);
+            *transferred = transferred
+                .checked_add(amount)
+                .ok_or(BCounterError::Overflow)?;
+            delta.transfers.insert((from, to), *transferred);
+        }
+        Ok(delta)
+    }
+
+    /// ### Merge delta
+    ///
+    /// Joins a delta returned by a mutator of another replica into the counter.
+    ///
+    /// * `delta` - Delta to join.
+    #[inline]
+    pub fn merge_delta(&mut self, delta: &Self) {
+        self.merge_from_state(delta);
+    }
+
+    /// ### Merge from state
+    ///
+    /// Merges the state of the counter with the state of another counter.
+    ///
+    /// * `other_counter` - State of another counter.
+    pub fn merge_from_state(&mut self, other_counter: &Self) {
+        self.increments.merge_from_state(&other_counter.increments);
+        self.decrements.merge_from_state(&other_counter.decrements);
+        for (nodes, amount) in &other_counter.transfers {
+            let current = self.transfers.entry(*nodes).or_insert(0);
+            *current = max(*current, *amount);
+        }
+    }
+
+    /// ### Count
+    ///
+    /// Returns the count of a node in a grow-only counter.
+    #[inline]
+    fn count(counter: &GCounter, node_id: UID) -> u64 {
+        counter.state.get(&node_id).copied().unwrap_or(0)
+    }
+}
+
+//#region Serialization
+impl VarSerialize for BCounter {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = self.increments.serialize_var();
+        encoded.extend(self.decrements.serialize_var());
+        encoded.extend(self.transfers.serialize_var());
+        encoded
+    }
+}
+
+impl VarDeserialize for BCounter {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (increments, rest) = GCounter::deserialize_var(encoded)?;
+        let (decrements, rest) = GCounter::deserialize_var(rest)?;
+        let (transfers, rest) = BTreeMap::deserialize_var(rest)?;
+        Ok((
+            Self {
+                increments,
+                decrements,
+                transfers,
+            },
+            rest,
+        ))
+    }
+}
+//#endregion
+
+//#region BCounterError
+/// ## BCounter error
+///
+/// Custom error related to bounded counter operations.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BCounterError {
+    InsufficientRights,
+    Overflow,
+}
+//#endregion
+
+#[cfg(test)]
+mod bcounter_tests {
+    use super::*;
+    use crate::serialization::test_var_serialization;
+
+    #[test]
+    fn decrements_are_bounded_by_rights() {
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut counter = BCounter::new();
+        counter.increment(nid_a, 3);
+
+        assert_eq!(
+            counter.decrement(nid_b, 1),
+            Err(BCounterError::InsufficientRights),
+            "Nodes without rights cannot decrement."
+        );
+        counter.decrement(nid_a, 2).unwrap();
+        assert_eq!(
+            counter.decrement(nid_a, 2),
+            Err(BCounterError::InsufficientRights)
+        );
+        assert_eq!(counter.get_value(), 1);
+        assert_eq!(counter.get_rights(nid_a), 1);
+    }
+
+    #[test]
+    fn concurrent_decrements_keep_the_bound() {
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut counter_a = BCounter::new();
+        counter_a.increment(nid_a, 4);
+        counter_a.transfer(nid_a, nid_b, 1).unwrap();
+        let mut counter_b = counter_a.clone();
+
+        counter_a.decrement(nid_a, 3).unwrap();
+        counter_b.decrement(nid_b, 1).unwrap();
+        assert_eq!(
+            counter_b.decrement(nid_b, 1),
+            Err(BCounterError::InsufficientRights)
+        );
+
+        let mut merged_b = counter_b.clone();
+        merged_b.merge_from_state(&counter_a);
+        counter_a.merge_from_state(&counter_b);
+        assert_eq!(counter_a, merged_b, "Merge should be commutative.");
+        assert_eq!(counter_a.get_value(), 0);
+    }
+
+    #[test]
+    fn deltas_converge_with_states() {
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut counter_a = BCounter::new();
+        let mut counter_b = BCounter::new();
+        let deltas = [
+            counter_a.increment(nid_a, 4),
+            counter_a.transfer(nid_a, nid_b, 3).unwrap(),
+            counter_a.transfer(nid_a, nid_b, 1).unwrap(),
+            counter_a.decrement(nid_b, 2).unwrap(),
+            counter_a.transfer(nid_a, nid_a, 0).unwrap(),
+        ];
+        for delta in deltas.iter().rev() {
+            counter_b.merge_delta(delta);
+        }
+        assert_eq!(counter_a, counter_b, "Deltas should commute.");
+        assert_eq!(counter_b.get_value(), 2);
+        assert_eq!(counter_b.get_rights(nid_b), 2);
+    }
+
+    #[test]
+    fn counts_saturate() {
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut counter = BCounter::new();
+        counter.increment(nid_a, u64::MAX);
+        counter.increment(nid_a, 1);
+        counter.increment(nid_b, 1);
+        assert_eq!(counter.get_value(), u64::MAX);
+        assert_eq!(counter.get_rights(nid_a), u64::MAX);
+
+        counter.transfer(nid_a, nid_b, u64::MAX).unwrap();
+        assert_eq!(counter.get_rights(nid_b), u64::MAX);
+        assert_eq!(counter.get_rights(nid_a), 0);
+        assert_eq!(
+            counter.transfer(nid_a, nid_b, 1),
+            Err(BCounterError::InsufficientRights)
+        );
+
+        let mut invalid = BCounter::new();
+        invalid.decrements.increment_by(nid_a, 1);
+        assert_eq!(
+            invalid.get_value(),
+            0,
+            "Invalid states should not underflow."
+        );
+        assert_eq!(invalid.get_rights(nid_a), 0);
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut counter = BCounter::new();
+        counter.increment(nid_a, 4);
+        counter.transfer(nid_a, nid_b, 2).unwrap();
+        counter.decrement(nid_b, 1).unwrap();
+        test_var_serialization(counter);
+    }
+}
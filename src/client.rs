@@ -1,8 +1,15 @@
 //! # Client interface
 //!
 //! Objects intended for usage in client-only environments.
-use super::{lwwregister::LWWRegister, time::Timestamp, uid::UID};
-use crate::serialization::Serialize;
+use super::{
+    flag::{DWFlag, EWFlag},
+    lwwregister::LWWRegister,
+    mvregister::MVRegister,
+    pncounter::PNCounter,
+    time::Timestamp,
+    uid::UID,
+};
+use crate::serialization::{Serialize, VarDeserialize, VarSerialize};
 use wasm_bindgen::prelude::*;
 
 /// ## Generate ID
@@ -26,3 +33,274 @@ pub fn create_bool_register(ts: Timestamp, value: bool) -> Vec<u8> {
     let register = LWWRegister::new(ts, value);
     Serialize::<9>::serialize(&register).into()
 }
+
+/// ## Create string register
+///
+/// Constructs a last-write-wins register over a string, serializes it and returns the encoded
+/// version of the register (the string is length-prefixed).
+///
+/// * `ts` - Timestamp marking the moment of creation of the register.
+/// * `value` - Initial value of the register.
+#[wasm_bindgen(js_name = createStringRegister)]
+pub fn create_string_register(ts: Timestamp, value: String) -> Vec<u8> {
+    LWWRegister::new(ts, value).serialize_var()
+}
+
+/// ## Create bytes register
+///
+/// Constructs a last-write-wins register over a byte array, serializes it and returns the
+/// encoded version of the register (the bytes are length-prefixed).
+///
+/// * `ts` - Timestamp marking the moment of creation of the register.
+/// * `value` - Initial value of the register.
+#[wasm_bindgen(js_name = createBytesRegister)]
+pub fn create_bytes_register(ts: Timestamp, value: Vec<u8>) -> Vec<u8> {
+    LWWRegister::new(ts, value).serialize_var()
+}
+
+/// ## Create integer register
+///
+/// Constructs a last-write-wins register over a 64-bit integer, serializes it and returns the
+/// encoded version of the register.
+///
+/// * `ts` - Timestamp marking the moment of creation of the register.
+/// * `value` - Initial value of the register.
+#[wasm_bindgen(js_name = createIntRegister)]
+pub fn create_int_register(ts: Timestamp, value: i64) -> Vec<u8> {
+    LWWRegister::new(ts, value).serialize_var()
+}
+
+/// ## Create float register
+///
+/// Constructs a last-write-wins register over a 64-bit float, serializes it and returns the
+/// encoded version of the register.
+///
+/// * `ts` - Timestamp marking the moment of creation of the register.
+/// * `value` - Initial value of the register.
+#[wasm_bindgen(js_name = createFloatRegister)]
+pub fn create_float_register(ts: Timestamp, value: f64) -> Vec<u8> {
+    LWWRegister::new(ts, value).serialize_var()
+}
+
+/// ## Create enable-wins flag
+///
+/// Constructs an enable-wins flag, serializes it and returns the encoded version of the flag.
+/// Concurrent enables and disables of the flag leave it enabled.
+///
+/// * `nid` - ID of the current node.
+/// * `value` - Initial value of the flag.
+#[wasm_bindgen(js_name = createEWFlag)]
+pub fn create_ew_flag(nid: UID, value: bool) -> Vec<u8> {
+    let mut flag = EWFlag::new();
+    flag.set(value, nid);
+    flag.serialize_var()
+}
+
+/// ## Create disable-wins flag
+///
+/// Constructs a disable-wins flag, serializes it and returns the encoded version of the flag.
+/// Concurrent enables and disables of the flag leave it disabled.
+///
+/// * `nid` - ID of the current node.
+/// * `value` - Initial value of the flag.
+#[wasm_bindgen(js_name = createDWFlag)]
+pub fn create_dw_flag(nid: UID, value: bool) -> Vec<u8> {
+    let mut flag = DWFlag::new();
+    flag.set(value, nid);
+    flag.serialize_var()
+}
+
+/// ## Set enable-wins flag
+///
+/// Enables or disables an encoded enable-wins flag.
+///
+/// * Throws a JS exception if the flag could not be deserialized.
+#[wasm_bindgen(js_name = setEWFlag)]
+pub fn set_ew_flag(encoded: Vec<u8>, nid: UID, value: bool) -> Vec<u8> {
+    let (mut flag, _) = EWFlag::deserialize_var(&encoded).unwrap_throw();
+    flag.set(value, nid);
+    flag.serialize_var()
+}
+
+/// ## Set disable-wins flag
+///
+/// Enables or disables an encoded disable-wins flag.
+///
+/// * Throws a JS exception if the flag could not be deserialized.
+#[wasm_bindgen(js_name = setDWFlag)]
+pub fn set_dw_flag(encoded: Vec<u8>, nid: UID, value: bool) -> Vec<u8> {
+    let (mut flag, _) = DWFlag::deserialize_var(&encoded).unwrap_throw();
+    flag.set(value, nid);
+    flag.serialize_var()
+}
+
+/// ## Merge enable-wins flags
+///
+/// Merges two encoded enable-wins flags and returns the encoded result.
+///
+/// * Throws a JS exception if any of the flags could not be deserialized.
+#[wasm_bindgen(js_name = mergeEWFlags)]
+pub fn merge_ew_flags(encoded: Vec<u8>, other_encoded: Vec<u8>) -> Vec<u8> {
+    let (mut flag, _) = EWFlag::deserialize_var(&encoded).unwrap_throw();
+    let (other, _) = EWFlag::deserialize_var(&other_encoded).unwrap_throw();
+    flag.merge(&other);
+    flag.serialize_var()
+}
+
+/// ## Merge disable-wins flags
+///
+/// Merges two encoded disable-wins flags and returns the encoded result.
+///
+/// * Throws a JS exception if any of the flags could not be deserialized.
+#[wasm_bindgen(js_name = mergeDWFlags)]
+pub fn merge_dw_flags(encoded: Vec<u8>, other_encoded: Vec<u8>) -> Vec<u8> {
+    let (mut flag, _) = DWFlag::deserialize_var(&encoded).unwrap_throw();
+    let (other, _) = DWFlag::deserialize_var(&other_encoded).unwrap_throw();
+    flag.merge(&other);
+    flag.serialize_var()
+}
+
+/// ## Get enable-wins flag value
+///
+/// Returns whether an encoded enable-wins flag is enabled.
+///
+/// * Throws a JS exception if the flag could not be deserialized.
+#[wasm_bindgen(js_name = getEWFlagValue)]
+pub fn get_ew_flag_value(encoded: Vec<u8>) -> bool {
+    EWFlag::deserialize_var(&encoded)
+        .unwrap_throw()
+        .0
+        .is_enabled()
+}
+
+/// ## Get disable-wins flag value
+///
+/// Returns whether an encoded disable-wins flag is enabled.
+///
+/// * Throws a JS exception if the flag could not be deserialized.
+#[wasm_bindgen(js_name = getDWFlagValue)]
+pub fn get_dw_flag_value(encoded: Vec<u8>) -> bool {
+    DWFlag::deserialize_var(&encoded)
+        .unwrap_throw()
+        .0
+        .is_enabled()
+}
+
+/// ## Create counter
+///
+/// Constructs a positive/negative counter with value 0, serializes it and returns the encoded
+/// version of the counter.
+#[wasm_bindgen(js_name = createCounter)]
+pub fn create_counter() -> Vec<u8> {
+    PNCounter::new(None).serialize_var()
+}
+
+/// ## Add to counter
+///
+/// Increments an encoded counter and returns the encoded result.
+///
+/// * `encoded` - Encoded counter.
+/// * `nid` - ID of the current node.
+/// * `amount` - Amount to add.
+/// * Throws a JS exception if the counter could not be deserialized.
+#[wasm_bindgen(js_name = addCounter)]
+pub fn add_counter(encoded: Vec<u8>, nid: UID, amount: u32) -> Vec<u8> {
+    let (mut counter, _) = PNCounter::deserialize_var(&encoded).unwrap_throw();
+    counter.add(nid, amount.into());
+    counter.serialize_var()
+}
+
+/// ## Subtract from counter
+///
+/// Decrements an encoded counter and returns the encoded result.
+///
+/// * `encoded` - Encoded counter.
+/// * `nid` - ID of the current node.
+/// * `amount` - Amount to subtract.
+/// * Throws a JS exception if the counter could not be deserialized.
+#[wasm_bindgen(js_name = subCounter)]
+pub fn sub_counter(encoded: Vec<u8>, nid: UID, amount: u32) -> Vec<u8> {
+    let (mut counter, _) = PNCounter::deserialize_var(&encoded).unwrap_throw();
+    counter.sub(nid, amount.into());
+    counter.serialize_var()
+}
+
+/// ## Merge counters
+///
+/// Merges two encoded counters and returns the encoded result.
+///
+/// * Throws a JS exception if any of the counters could not be deserialized.
+#[wasm_bindgen(js_name = mergeCounters)]
+pub fn merge_counters(encoded: Vec<u8>, other_encoded: Vec<u8>) -> Vec<u8> {
+    let (mut counter, _) = PNCounter::deserialize_var(&encoded).unwrap_throw();
+    let (other, _) = PNCounter::deserialize_var(&other_encoded).unwrap_throw();
+    counter.merge_from_state(&other);
+    counter.serialize_var()
+}
+
+/// ## Get counter value
+///
+/// Returns the (possibly negative) value of an encoded counter as a JS number.
+///
+/// * Throws a JS exception if the counter could not be deserialized.
+#[wasm_bindgen(js_name = getCounterValue)]
+pub fn get_counter_value(encoded: Vec<u8>) -> f64 {
+    PNCounter::deserialize_var(&encoded)
+        .unwrap_throw()
+        .0
+        .get_value() as f64
+}
+
+/// ## Create bool multi-value register
+///
+/// Constructs a multi-value register over a boolean value, serializes it and returns the encoded
+/// version of the register.
+///
+/// * `nid` - ID of the current node.
+/// * `value` - Initial value of the register.
+#[wasm_bindgen(js_name = createBoolMVRegister)]
+pub fn create_bool_mv_register(nid: UID, value: bool) -> Vec<u8> {
+    let mut register = MVRegister::new();
+    register.set(value, nid);
+    register.serialize_var()
+}
+
+/// ## Set bool multi-value register
+///
+/// Writes a new value to an encoded multi-value register, superseding all of its current values.
+///
+/// * `encoded` - Encoded register.
+/// * `nid` - ID of the current node.
+/// * `value` - New value of the register.
+/// * Throws a JS exception if the register could not be deserialized.
+#[wasm_bindgen(js_name = setBoolMVRegister)]
+pub fn set_bool_mv_register(encoded: Vec<u8>, nid: UID, value: bool) -> Vec<u8> {
+    let (mut register, _) = MVRegister::<bool>::deserialize_var(&encoded).unwrap_throw();
+    register.set(value, nid);
+    register.serialize_var()
+}
+
+/// ## Merge bool multi-value registers
+///
+/// Merges two encoded multi-value registers and returns the encoded result.
+///
+/// * Throws a JS exception if any of the registers could not be deserialized.
+#[wasm_bindgen(js_name = mergeBoolMVRegisters)]
+pub fn merge_bool_mv_registers(encoded: Vec<u8>, other_encoded: Vec<u8>) -> Vec<u8> {
+    let (mut register, _) = MVRegister::<bool>::deserialize_var(&encoded).unwrap_throw();
+    let (other, _) = MVRegister::<bool>::deserialize_var(&other_encoded).unwrap_throw();
+    register.merge(&other);
+    register.serialize_var()
+}
+
+/// ## Get bool multi-value register values
+///
+/// Returns the concurrent values of an encoded multi-value register, so that conflicts can be
+/// presented to the user.
+///
+/// * Throws a JS exception if the register could not be deserialized.
+#[wasm_bindgen(js_name = getBoolMVRegisterValues)]
+pub fn get_bool_mv_register_values(encoded: Vec<u8>) -> js_sys::Array {
+    let (register, _) = MVRegister::<bool>::deserialize_var(&encoded).unwrap_throw();
+    register.values().into_iter().map(JsValue::from).collect()
+}
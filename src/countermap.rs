@@ -0,0 +1,211 @@
+//! # CounterMap CRDT
+//!
+//! Map of named counters (a _PN-Map_): every key holds a [`PNCounter`], created on first use.
+//!
+//! Useful for counting per item, e.g. votes per option of a poll. Keys are never removed, and
+//! merging two maps merges the counters of each key.
+//!
+//! ### Usage
+//!
+//! ```rust
+//! use crdts::countermap::CounterMap;
+//! use crdts::uid::UID;
+//!
+//! let (nid_a, nid_b) = (UID::new(), UID::new());
+//!
+//! let mut votes_a = CounterMap::new();
+//! let mut votes_b = CounterMap::new();
+//! votes_a.increment(&"yes", nid_a, 3);
+//! votes_b.increment(&"yes", nid_b, 1);
+//! votes_b.decrement(&"no", nid_b, 2);
+//!
+//! votes_a.merge(&votes_b);
+//! assert_eq!(votes_a.get(&"yes"), 4);
+//! assert_eq!(votes_a.get(&"no"), -2);
+//! ```
+use crate::{
+    pncounter::PNCounter,
+    serialization::{DeserializeError, VarDeserialize, VarSerialize},
+    uid::UID,
+};
+use std::collections::BTreeMap;
+
+/// ## CounterMap
+///
+/// Map from keys of a generic ordered type to counters.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CounterMap<K: Ord + Clone> {
+    /// ### Counters
+    ///
+    /// Counter of each key that has been used.
+    counters: BTreeMap<K, PNCounter>,
+}
+
+impl<K: Ord + Clone> Default for CounterMap<K> {
+    fn default() -> Self {
+        Self {
+            counters: BTreeMap::new(),
+        }
+    }
+}
+
+impl<K: Ord + Clone> CounterMap<K> {
+    /// ### New CounterMap
+    ///
+    /// Creates a new empty map.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ### Get
+    ///
+    /// Returns the value of the counter of a key, or 0 if the key has not been used.
+    pub fn get(&self, key: &K) -> i64 {
+        self.counters.get(key).map_or(0, PNCounter::get_value)
+    }
+
+    /// ### Increment
+    ///
+    /// Increments the counter of a key, creating it if needed.
+    ///
+    /// * `key` - Key of the counter.
+    /// * `nid` - ID of the current node.
+    /// * `amount` - Amount to add.
+    pub fn increment(&mut self, key: &K, nid: UID, amount: u64) {
+        self.counter_mut(key).add(nid, amount);
+    }
+
+    /// ### Decrement
+    ///
+    /// Decrements the counter of a key, creating it if needed.
+    ///
+    /// * `key` - Key of the counter.
+    /// * `nid` - ID of the current node.
+    /// * `amount` - Amount to subtract.
+    pub fn decrement(&mut self, key: &K, nid: UID, amount: u64) {
+        self.counter_mut(key).sub(nid, amount);
+    }
+
+    /// ### Contains key
+    ///
+    /// Returns whether the counter of a key has been used.
+    #[inline]
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.counters.contains_key(key)
+    }
+
+    /// ### Iterate
+    ///
+    /// Returns an iterator over the keys and counter values, in ascending key order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, i64)> {
+        self.counters
+            .iter()
+            .map(|(key, counter)| (key, counter.get_value()))
+    }
+
+    /// ### Length
+    ///
+    /// Returns the number of keys in the map.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.counters.len()
+    }
+
+    /// ### Is empty
+    ///
+    /// Returns whether no key has been used.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.counters.is_empty()
+    }
+
+    /// ### Merge
+    ///
+    /// Merges another map into the current one, merging the counters key by key.
+    ///
+    /// * `other` - State of another map.
+    pub fn merge(&mut self, other: &Self) {
+        for (key, counter) in &other.counters {
+            self.counter_mut(key).merge_from_state(counter);
+        }
+    }
+
+    /// ### Counter (mutable)
+    ///
+    /// Returns the counter of a key, creating it if needed.
+    fn counter_mut(&mut self, key: &K) -> &mut PNCounter {
+        self.counters
+            .entry(key.clone())
+            .or_insert_with(|| PNCounter::new(None))
+    }
+}
+
+//#region Serialization
+impl<K: Ord + Clone + VarSerialize> VarSerialize for CounterMap<K> {
+    fn serialize_var(&self) -> Vec<u8> {
+        self.counters.serialize_var()
+    }
+}
+
+impl<K: Ord + Clone + VarDeserialize> VarDeserialize for CounterMap<K> {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (counters, rest) = BTreeMap::deserialize_var(encoded)?;
+        Ok((Self { counters }, rest))
+    }
+}
+//#endregion
+
+#[cfg(test)]
+mod countermap_tests {
+    use super::*;
+    use crate::serialization::test_var_serialization;
+
+    #[test]
+    fn counters_are_created_lazily() {
+        let nid = UID::new();
+        let mut map = CounterMap::new();
+        assert_eq!(map.get(&1u8), 0);
+        assert!(map.is_empty());
+
+        map.increment(&1, nid, 5);
+        map.decrement(&1, nid, 7);
+        map.decrement(&2, nid, 1);
+        assert_eq!(map.iter().collect::<Vec<_>>(), vec![(&1, -2), (&2, -1)]);
+        assert!(map.contains_key(&2) && !map.contains_key(&3));
+    }
+
+    #[test]
+    fn merge_combines_counters_per_key() {
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut map_a = CounterMap::new();
+        map_a.increment(&String::from("x"), nid_a, 2);
+        let mut map_b = map_a.clone();
+
+        map_a.increment(&String::from("x"), nid_a, 1);
+        map_b.increment(&String::from("x"), nid_b, 4);
+        map_b.decrement(&String::from("y"), nid_b, 3);
+
+        let mut merged_b = map_b.clone();
+        merged_b.merge(&map_a);
+        map_a.merge(&map_b);
+        map_a.merge(&map_b);
+
+        assert_eq!(map_a.get(&String::from("x")), 7);
+        assert_eq!(map_a.get(&String::from("y")), -3);
+        assert_eq!(
+            map_a, merged_b,
+            "Merge should be commutative and idempotent."
+        );
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let nid = UID::new();
+        let mut map = CounterMap::new();
+        map.increment(&String::from("a"), nid, 3);
+        map.decrement(&String::from("b"), nid, 1);
+        test_var_serialization(map);
+        test_var_serialization(CounterMap::<u32>::new());
+    }
+}
@@ -0,0 +1,1234 @@
+//! # Document CRDT
+//!
+//! JSON-like document CRDT made of nested maps, lists, counters and registers.
+//!
+//! * **Maps** hold named slots. Writing a slot replaces its content, concurrent writes are
+//!   decided by last-write-wins. Deleting a key writes a tombstone into the slot.
+//! * **Lists** are [`RGA`] sequences of slots.
+//! * **Counters** are [`PNCounter`]s.
+//! * **Leaves** (null, booleans, numbers and strings) are [`LWWRegister`]s over [`Scalar`]s.
+//!   Concurrent writes of leaves are merged with [`LWWRegister::merge`], using the IDs of the
+//!   writing nodes to break ties, so they resolve like standalone registers.
+//!
+//! Every slot remembers the [`Dot`] of the operation that last wrote it. Operations address
+//! nested values by the chain of slots leading to them, so an update to a value that has been
+//! replaced concurrently is discarded instead of being applied to the new value.
+//!
+//! ### Operation log
+//!
+//! Local updates return a [`DocOp`] that can be encoded and applied on other replicas. Every
+//! applied operation is recorded in the operation log of the document, which doubles as the
+//! persistent state: a document is encoded as its log and decoded by replaying it.
+//!
+//! Operations are expected to be delivered in causal order, so that the values they address have
+//! already been created.
+//!
+//! ### Usage
+//!
+//! ```rust
+//! use crdts::document::{Document, Value};
+//! use crdts::time::hlc::{HybridLogicalClock, SysTimeHLC};
+//! use crdts::uid::UID;
+//!
+//! let mut hlc = SysTimeHLC::default();
+//! let nid = UID::new();
+//!
+//! let mut doc = Document::new();
+//! doc.set(&["todos".into()], Value::List(vec![]), nid, hlc.generate_timestamp()).unwrap();
+//! let op = doc
+//!     .insert(&["todos".into(), 0.into()], Value::from("Buy milk"), nid, hlc.generate_timestamp())
+//!     .unwrap();
+//!
+//! let mut replica = Document::new();
+//! replica.merge(&doc).unwrap();
+//! assert_eq!(replica.get(&["todos".into(), 0.into()]), Some(Value::from("Buy milk")));
+//! ```
+use crate::{
+    dot::Dot,
+    lwwregister::LWWRegister,
+    pncounter::PNCounter,
+    rga::RGA,
+    serialization::{DeserializeError, VarDeserialize, VarSerialize},
+    time::timestamp::Timestamp,
+    uid::UID,
+};
+use std::cmp::max;
+use std::collections::{BTreeMap, BTreeSet};
+use wasm_bindgen::prelude::*;
+
+//#region Values
+/// ## Value
+///
+/// Plain (non-replicated) snapshot of a document value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Counter(i64),
+    List(Vec<Value>),
+    Map(BTreeMap<String, Value>),
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Number(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(String::from(value))
+    }
+}
+
+impl Value {
+    /// ### To JS value
+    ///
+    /// Converts the value to a plain JS value. Counters are converted to numbers.
+    pub fn to_js(&self) -> JsValue {
+        match self {
+            Value::Null => JsValue::NULL,
+            Value::Bool(value) => JsValue::from_bool(*value),
+            Value::Number(value) => JsValue::from_f64(*value),
+            Value::String(value) => JsValue::from_str(value),
+            Value::Counter(value) => JsValue::from_f64(*value as f64),
+            Value::List(values) => values
+                .iter()
+                .map(Value::to_js)
+                .collect::<js_sys::Array>()
+                .into(),
+            Value::Map(fields) => {
+                let obj = js_sys::Object::new();
+                for (key, value) in fields {
+                    js_sys::Reflect::set(&obj, &JsValue::from_str(key), &value.to_js())
+                        .unwrap_throw();
+                }
+                obj.into()
+            }
+        }
+    }
+
+    /// ### From JS value
+    ///
+    /// Converts a plain JS value (as produced by `JSON.parse`) to a value.
+    ///
+    /// * `undefined` is treated as `null`.
+    /// * Throws [`DocumentError::InvalidValue`] if the value cannot be represented.
+    pub fn from_js(value: &JsValue) -> Result<Self, DocumentError> {
+        if value.is_null() || value.is_undefined() {
+            Ok(Value::Null)
+        } else if let Some(value) = value.as_bool() {
+            Ok(Value::Bool(value))
+        } else if let Some(value) = value.as_f64() {
+            Ok(Value::Number(value))
+        } else if let Some(value) = value.as_string() {
+            Ok(Value::String(value))
+        } else if js_sys::Array::is_array(value) {
+            js_sys::Array::from(value)
+                .iter()
+                .map(|item| Value::from_js(&item))
+                .collect::<Result<Vec<Value>, DocumentError>>()
+                .map(Value::List)
+        } else if value.is_object() {
+            let obj = js_sys::Object::from(value.clone());
+            let mut fields = BTreeMap::new();
+            for key in js_sys::Object::keys(&obj).iter() {
+                let field =
+                    js_sys::Reflect::get(&obj, &key).map_err(|_| DocumentError::InvalidValue)?;
+                let key = key.as_string().ok_or(DocumentError::InvalidValue)?;
+                fields.insert(key, Value::from_js(&field)?);
+            }
+            Ok(Value::Map(fields))
+        } else {
+            Err(DocumentError::InvalidValue)
+        }
+    }
+}
+
+impl VarSerialize for Value {
+    fn serialize_var(&self) -> Vec<u8> {
+        let (kind, payload) = match self {
+            Value::Null => (0u8, vec![]),
+            Value::Bool(value) => (1, value.serialize_var()),
+            Value::Number(value) => (2, value.serialize_var()),
+            Value::String(value) => (3, value.serialize_var()),
+            Value::Counter(value) => (4, value.serialize_var()),
+            Value::List(values) => (5, values.serialize_var()),
+            Value::Map(fields) => (6, fields.serialize_var()),
+        };
+        let mut encoded = vec![kind];
+        encoded.extend(payload);
+        encoded
+    }
+}
+
+impl VarDeserialize for Value {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (kind, rest) = u8::deserialize_var(encoded)?;
+        match kind {
+            0 => Ok((Value::Null, rest)),
+            1 => bool::deserialize_var(rest).map(|(v, rest)| (Value::Bool(v), rest)),
+            2 => f64::deserialize_var(rest).map(|(v, rest)| (Value::Number(v), rest)),
+            3 => String::deserialize_var(rest).map(|(v, rest)| (Value::String(v), rest)),
+            4 => i64::deserialize_var(rest).map(|(v, rest)| (Value::Counter(v), rest)),
+            5 => Vec::deserialize_var(rest).map(|(v, rest)| (Value::List(v), rest)),
+            6 => BTreeMap::deserialize_var(rest).map(|(v, rest)| (Value::Map(v), rest)),
+            _ => Err(DeserializeError::InvalidValue),
+        }
+    }
+}
+
+/// ## Scalar
+///
+/// Value of a leaf node.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Scalar {
+    #[default]
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+}
+//#endregion
+
+//#region Paths
+/// ## Path segment
+///
+/// Segment of a local path into the document: a map key or a list index.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+impl From<&str> for PathSegment {
+    fn from(key: &str) -> Self {
+        PathSegment::Key(String::from(key))
+    }
+}
+
+impl From<usize> for PathSegment {
+    fn from(index: usize) -> Self {
+        PathSegment::Index(index)
+    }
+}
+
+/// ## Operation key
+///
+/// Replica-independent key of a slot: a map key or the ID of a list element.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum OpKey {
+    Field(String),
+    Element(Dot),
+}
+
+impl VarSerialize for OpKey {
+    fn serialize_var(&self) -> Vec<u8> {
+        let (kind, payload) = match self {
+            OpKey::Field(key) => (0u8, key.serialize_var()),
+            OpKey::Element(id) => (1, id.serialize_var()),
+        };
+        let mut encoded = vec![kind];
+        encoded.extend(payload);
+        encoded
+    }
+}
+
+impl VarDeserialize for OpKey {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (kind, rest) = u8::deserialize_var(encoded)?;
+        match kind {
+            0 => String::deserialize_var(rest).map(|(key, rest)| (OpKey::Field(key), rest)),
+            1 => Dot::deserialize_var(rest).map(|(id, rest)| (OpKey::Element(id), rest)),
+            _ => Err(DeserializeError::InvalidValue),
+        }
+    }
+}
+//#endregion
+
+//#region Operations
+/// ## Parents
+///
+/// Chain of slots leading from the root to the target of an operation, each given by its key and
+/// the ID of the operation that last wrote it.
+pub type Parents = Vec<(OpKey, Dot)>;
+
+/// ## Document operation kind
+#[derive(Clone, Debug, PartialEq)]
+pub enum DocOpKind {
+    /// ### Set
+    ///
+    /// Writes a value into a slot of the target map or list.
+    Set { key: OpKey, value: Value },
+
+    /// ### Delete
+    ///
+    /// Deletes a slot of the target map or list.
+    Delete { key: OpKey },
+
+    /// ### Insert
+    ///
+    /// Inserts a value into the target list right after the element `origin` (or at the head).
+    Insert { origin: Option<Dot>, value: Value },
+
+    /// ### Increment
+    ///
+    /// Increments (or decrements, if negative) the target counter.
+    Increment { amount: i64 },
+}
+
+/// ## Document operation
+///
+/// Operation generated by a local update that can be sent to other replicas.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DocOp {
+    /// ### ID
+    ///
+    /// Unique ID of the operation.
+    pub id: Dot,
+
+    /// ### Parents
+    ///
+    /// Slots leading to the target of the operation.
+    pub parents: Parents,
+
+    /// ### Kind
+    ///
+    /// Update performed on the target.
+    pub kind: DocOpKind,
+}
+
+impl VarSerialize for DocOp {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = self.id.serialize_var();
+        encoded.extend(self.parents.serialize_var());
+        match &self.kind {
+            DocOpKind::Set { key, value } => {
+                encoded.push(0);
+                encoded.extend(key.serialize_var());
+                encoded.extend(value.serialize_var());
+            }
+            DocOpKind::Delete { key } => {
+                encoded.push(1);
+                encoded.extend(key.serialize_var());
+            }
+            DocOpKind::Insert { origin, value } => {
+                encoded.push(2);
+                encoded.extend(origin.serialize_var());
+                encoded.extend(value.serialize_var());
+            }
+            DocOpKind::Increment { amount } => {
+                encoded.push(3);
+                encoded.extend(amount.serialize_var());
+            }
+        }
+        encoded
+    }
+}
+
+impl VarDeserialize for DocOp {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (id, rest) = Dot::deserialize_var(encoded)?;
+        let (parents, rest) = Vec::deserialize_var(rest)?;
+        let (kind, rest) = u8::deserialize_var(rest)?;
+        let (kind, rest) = match kind {
+            0 => {
+                let (key, rest) = OpKey::deserialize_var(rest)?;
+                let (value, rest) = Value::deserialize_var(rest)?;
+                (DocOpKind::Set { key, value }, rest)
+            }
+            1 => {
+                let (key, rest) = OpKey::deserialize_var(rest)?;
+                (DocOpKind::Delete { key }, rest)
+            }
+            2 => {
+                let (origin, rest) = Option::deserialize_var(rest)?;
+                let (value, rest) = Value::deserialize_var(rest)?;
+                (DocOpKind::Insert { origin, value }, rest)
+            }
+            3 => {
+                let (amount, rest) = i64::deserialize_var(rest)?;
+                (DocOpKind::Increment { amount }, rest)
+            }
+            _ => return Err(DeserializeError::InvalidValue),
+        };
+        Ok((Self { id, parents, kind }, rest))
+    }
+}
+//#endregion
+
+//#region Nodes
+/// ## Node
+///
+/// Replicated value stored in a slot.
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Register(LWWRegister<Scalar>),
+    Counter(PNCounter),
+    Map(BTreeMap<String, Slot>),
+    List(RGA<Slot>),
+}
+
+/// ## Slot
+///
+/// Position holding a node, tagged with the ID of the operation that last wrote it.
+#[derive(Clone, Debug, PartialEq)]
+struct Slot {
+    id: Dot,
+    node: Option<Node>,
+}
+
+impl Slot {
+    /// ### Assign
+    ///
+    /// Replaces the content of the slot if the other write is newer. Registers are merged, the ID
+    /// of each slot telling which node wrote its register.
+    fn assign(&mut self, other: Slot) {
+        match (&mut self.node, &other.node) {
+            (Some(Node::Register(register)), Some(Node::Register(other_register))) => {
+                register.merge(other_register, self.id.nid, other.id.nid);
+                self.id = max(self.id, other.id);
+            }
+            _ if other.id > self.id => *self = other,
+            _ => {}
+        }
+    }
+}
+
+impl Node {
+    /// ### Materialize
+    ///
+    /// Builds the node for a value written by operation `id`. List elements get IDs with
+    /// consecutive timestamps after `last_ts`, which is updated accordingly. It must start at
+    /// the timestamp of the operation, so that all replicas derive the same IDs.
+    fn materialize(value: &Value, id: Dot, last_ts: &mut Timestamp) -> Node {
+        let register = |scalar| Node::Register(LWWRegister::new(id.ts, scalar));
+        match value {
+            Value::Null => register(Scalar::Null),
+            Value::Bool(value) => register(Scalar::Bool(*value)),
+            Value::Number(value) => register(Scalar::Number(*value)),
+            Value::String(value) => register(Scalar::String(value.clone())),
+            Value::Counter(value) => {
+                let mut counter = PNCounter::new(None);
+                increment_counter(&mut counter, id.nid, *value);
+                Node::Counter(counter)
+            }
+            Value::List(values) => {
+                let mut list = RGA::new();
+                let mut origin = None;
+                for value in values {
+                    last_ts.increase_counter();
+                    let element_id = Dot::new(id.nid, *last_ts);
+                    let slot = Slot {
+                        id: element_id,
+                        node: Some(Node::materialize(value, element_id, last_ts)),
+                    };
+                    list.insert_after(origin, slot, element_id.nid, element_id.ts)
+                        .unwrap_or_else(|_| unreachable!("The origin was just inserted."));
+                    origin = Some(element_id);
+                }
+                Node::List(list)
+            }
+            Value::Map(fields) => Node::Map(
+                fields
+                    .iter()
+                    .map(|(key, value)| {
+                        let node = Node::materialize(value, id, last_ts);
+                        (
+                            key.clone(),
+                            Slot {
+                                id,
+                                node: Some(node),
+                            },
+                        )
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// ### To value
+    ///
+    /// Returns a plain snapshot of the node.
+    fn to_value(&self) -> Value {
+        match self {
+            Node::Register(register) => match register.get_value() {
+                Scalar::Null => Value::Null,
+                Scalar::Bool(value) => Value::Bool(value),
+                Scalar::Number(value) => Value::Number(value),
+                Scalar::String(value) => Value::String(value),
+            },
+            Node::Counter(counter) => Value::Counter(counter.get_value()),
+            Node::Map(fields) => Value::Map(
+                fields
+                    .iter()
+                    .filter_map(|(key, slot)| Some((key.clone(), slot.node.as_ref()?.to_value())))
+                    .collect(),
+            ),
+            Node::List(list) => Value::List(
+                list.iter()
+                    .filter_map(|slot| Some(slot.node.as_ref()?.to_value()))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// ### Child
+    ///
+    /// Returns the slot addressed by a local path segment.
+    fn child(&self, segment: &PathSegment) -> Option<&Slot> {
+        match (self, segment) {
+            (Node::Map(fields), PathSegment::Key(key)) => fields.get(key),
+            (Node::List(list), PathSegment::Index(index)) => list.get(*index),
+            _ => None,
+        }
+    }
+
+    /// ### Operation key
+    ///
+    /// Converts a local path segment into a replica-independent key.
+    fn op_key(&self, segment: &PathSegment) -> Result<OpKey, DocumentError> {
+        match (self, segment) {
+            (Node::Map(_), PathSegment::Key(key)) => Ok(OpKey::Field(key.clone())),
+            (Node::List(list), PathSegment::Index(index)) => list
+                .id_at(*index)
+                .map(OpKey::Element)
+                .ok_or(DocumentError::InvalidPath),
+            _ => Err(DocumentError::TypeMismatch),
+        }
+    }
+}
+
+/// ## Increment counter
+///
+/// Adds a (possibly negative) amount to a counter on behalf of a node.
+fn increment_counter(counter: &mut PNCounter, nid: UID, amount: i64) {
+    if amount > 0 {
+        counter.add(nid, amount.unsigned_abs());
+    } else {
+        counter.sub(nid, amount.unsigned_abs());
+    }
+}
+//#endregion
+
+/// ## Document
+///
+/// JSON-like replicated document.
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Document {
+    /// ### Root
+    ///
+    /// Root map of the document.
+    root: Node,
+
+    /// ### Log
+    ///
+    /// Operations applied to the document, in order of application.
+    log: Vec<DocOp>,
+
+    /// ### Applied
+    ///
+    /// IDs of the operations in the log.
+    applied: BTreeSet<Dot>,
+
+    /// ### Last timestamp
+    ///
+    /// Largest timestamp used by any ID in the document. Local operations always get a larger
+    /// timestamp.
+    last_ts: Timestamp,
+}
+
+impl Default for Document {
+    fn default() -> Self {
+        Self {
+            root: Node::Map(BTreeMap::new()),
+            log: Vec::new(),
+            applied: BTreeSet::new(),
+            last_ts: Timestamp::default(),
+        }
+    }
+}
+
+impl Document {
+    /// ### New document
+    ///
+    /// Creates a new document with an empty root map.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ### Get
+    ///
+    /// Returns a plain snapshot of the value at a path, or `None` if there is no value.
+    pub fn get(&self, path: &[PathSegment]) -> Option<Value> {
+        Some(self.node_at(path)?.to_value())
+    }
+
+    /// ### To value
+    ///
+    /// Returns a plain snapshot of the whole document.
+    pub fn to_value(&self) -> Value {
+        self.root.to_value()
+    }
+
+    /// ### Log
+    ///
+    /// Returns the operations applied to the document, in order of application.
+    #[inline]
+    pub fn log(&self) -> &[DocOp] {
+        &self.log
+    }
+
+    /// ### Set
+    ///
+    /// Writes a value at a path, replacing the current value.
+    ///
+    /// * `path` - Path to a map key or to an existing list element.
+    /// * `value` - Value to write. Maps and lists are created with the given content.
+    /// * `nid` - ID of the current node.
+    /// * `ts` - Timestamp of the operation.
+    /// * Returns the operation to be sent to other replicas.
+    pub fn set(
+        &mut self,
+        path: &[PathSegment],
+        value: Value,
+        nid: UID,
+        ts: Timestamp,
+    ) -> Result<DocOp, DocumentError> {
+        let (segment, parent_path) = path.split_last().ok_or(DocumentError::InvalidPath)?;
+        let (parents, parent) = self.resolve(parent_path)?;
+        let key = parent.op_key(segment)?;
+        self.apply_local(parents, DocOpKind::Set { key, value }, nid, ts)
+    }
+
+    /// ### Delete
+    ///
+    /// Deletes the value at a path.
+    ///
+    /// * `path` - Path to a map key or to an existing list element.
+    /// * Returns the operation to be sent to other replicas.
+    pub fn delete(
+        &mut self,
+        path: &[PathSegment],
+        nid: UID,
+        ts: Timestamp,
+    ) -> Result<DocOp, DocumentError> {
+        let (segment, parent_path) = path.split_last().ok_or(DocumentError::InvalidPath)?;
+        let (parents, parent) = self.resolve(parent_path)?;
+        let key = parent.op_key(segment)?;
+        self.apply_local(parents, DocOpKind::Delete { key }, nid, ts)
+    }
+
+    /// ### Insert
+    ///
+    /// Inserts a value into a list.
+    ///
+    /// * `path` - Path to the list followed by the index at which the value is inserted.
+    /// * Returns the operation to be sent to other replicas.
+    pub fn insert(
+        &mut self,
+        path: &[PathSegment],
+        value: Value,
+        nid: UID,
+        ts: Timestamp,
+    ) -> Result<DocOp, DocumentError> {
+        let (segment, list_path) = path.split_last().ok_or(DocumentError::InvalidPath)?;
+        let (parents, list) = self.resolve(list_path)?;
+        let origin = match (list, segment) {
+            (Node::List(_), PathSegment::Index(0)) => None,
+            (Node::List(list), PathSegment::Index(index)) => {
+                Some(list.id_at(index - 1).ok_or(DocumentError::InvalidPath)?)
+            }
+            _ => return Err(DocumentError::TypeMismatch),
+        };
+        self.apply_local(parents, DocOpKind::Insert { origin, value }, nid, ts)
+    }
+
+    /// ### Increment
+    ///
+    /// Increments (or decrements, if negative) the counter at a path.
+    ///
+    /// * Returns the operation to be sent to other replicas.
+    pub fn increment(
+        &mut self,
+        path: &[PathSegment],
+        amount: i64,
+        nid: UID,
+        ts: Timestamp,
+    ) -> Result<DocOp, DocumentError> {
+        let (parents, counter) = self.resolve(path)?;
+        if !matches!(counter, Node::Counter(_)) {
+            return Err(DocumentError::TypeMismatch);
+        }
+        self.apply_local(parents, DocOpKind::Increment { amount }, nid, ts)
+    }
+
+    /// ### Apply
+    ///
+    /// Applies an operation (usually received from another replica) and records it in the log.
+    /// Applying the same operation more than once has no further effect.
+    ///
+    /// * Operations whose target has been replaced or deleted are recorded without effect.
+    /// * Throws [`DocumentError`] if the operation does not match the structure of the document.
+    pub fn apply(&mut self, op: &DocOp) -> Result<(), DocumentError> {
+        if self.applied.contains(&op.id) {
+            return Ok(());
+        }
+        let mut last_ts = op.id.ts;
+        if let Some(target) = self.target_mut(&op.parents) {
+            match (&op.kind, target) {
+                (DocOpKind::Set { key, value }, target) => {
+                    let node = Node::materialize(value, op.id, &mut last_ts);
+                    let slot = Slot {
+                        id: op.id,
+                        node: Some(node),
+                    };
+                    Self::assign(target, key, slot)?;
+                }
+                (
+                    DocOpKind::Delete {
+                        key: OpKey::Element(id),
+                    },
+                    Node::List(list),
+                ) => {
+                    list.delete(*id)
+                        .map_err(|_| DocumentError::UnknownElement)?;
+                }
+                (DocOpKind::Delete { key }, target) => {
+                    let slot = Slot {
+                        id: op.id,
+                        node: None,
+                    };
+                    Self::assign(target, key, slot)?;
+                }
+                (DocOpKind::Insert { origin, value }, Node::List(list)) => {
+                    let node = Node::materialize(value, op.id, &mut last_ts);
+                    let slot = Slot {
+                        id: op.id,
+                        node: Some(node),
+                    };
+                    list.insert_after(*origin, slot, op.id.nid, op.id.ts)
+                        .map_err(|_| DocumentError::UnknownElement)?;
+                }
+                (DocOpKind::Increment { amount }, Node::Counter(counter)) => {
+                    increment_counter(counter, op.id.nid, *amount);
+                }
+                _ => return Err(DocumentError::TypeMismatch),
+            }
+        }
+        self.last_ts = max(self.last_ts, last_ts);
+        self.applied.insert(op.id);
+        self.log.push(op.clone());
+        Ok(())
+    }
+
+    /// ### Merge
+    ///
+    /// Merges another document into the current one by applying the operations of its log.
+    pub fn merge(&mut self, other: &Self) -> Result<(), DocumentError> {
+        for op in &other.log {
+            self.apply(op)?;
+        }
+        Ok(())
+    }
+
+    /// ### From JS value
+    ///
+    /// Creates a document from a plain JS object, setting each of its keys.
+    ///
+    /// * Throws [`DocumentError::InvalidValue`] if the value is not an object.
+    pub fn from_js(value: &JsValue, nid: UID, ts: Timestamp) -> Result<Self, DocumentError> {
+        let mut doc = Self::new();
+        match Value::from_js(value)? {
+            Value::Map(fields) => {
+                for (key, value) in fields {
+                    doc.set(&[PathSegment::Key(key)], value, nid, ts)?;
+                }
+                Ok(doc)
+            }
+            _ => Err(DocumentError::InvalidValue),
+        }
+    }
+
+    /// ### To JS value
+    ///
+    /// Returns the document as a plain JS object.
+    #[inline]
+    pub fn to_js(&self) -> JsValue {
+        self.to_value().to_js()
+    }
+
+    /// ### Apply local
+    ///
+    /// Builds an operation with a fresh ID and applies it.
+    fn apply_local(
+        &mut self,
+        parents: Parents,
+        kind: DocOpKind,
+        nid: UID,
+        ts: Timestamp,
+    ) -> Result<DocOp, DocumentError> {
+        let mut next_ts = self.last_ts;
+        next_ts.increase_counter();
+        let op = DocOp {
+            id: Dot::new(nid, max(ts, next_ts)),
+            parents,
+            kind,
+        };
+        self.apply(&op)?;
+        Ok(op)
+    }
+
+    /// ### Node at path
+    ///
+    /// Returns the node addressed by a local path.
+    fn node_at(&self, path: &[PathSegment]) -> Option<&Node> {
+        let mut node = &self.root;
+        for segment in path {
+            node = node.child(segment)?.node.as_ref()?;
+        }
+        Some(node)
+    }
+
+    /// ### Resolve
+    ///
+    /// Converts a local path into the chain of slots leading to its node.
+    fn resolve(&self, path: &[PathSegment]) -> Result<(Parents, &Node), DocumentError> {
+        let mut parents = Vec::with_capacity(path.len());
+        let mut node = &self.root;
+        for segment in path {
+            let key = node.op_key(segment)?;
+            let slot = node.child(segment).ok_or(DocumentError::InvalidPath)?;
+            parents.push((key, slot.id));
+            node = slot.node.as_ref().ok_or(DocumentError::InvalidPath)?;
+        }
+        Ok((parents, node))
+    }
+
+    /// ### Target
+    ///
+    /// Returns the node addressed by a chain of slots, or `None` if any of the slots has been
+    /// written by a different operation.
+    fn target_mut(&mut self, parents: &[(OpKey, Dot)]) -> Option<&mut Node> {
+        let mut node = &mut self.root;
+        for (key, slot_id) in parents {
+            let slot = match (node, key) {
+                (Node::Map(fields), OpKey::Field(key)) => fields.get_mut(key)?,
+                (Node::List(list), OpKey::Element(id)) => list.get_mut_by_id(id)?,
+                _ => return None,
+            };
+            if slot.id != *slot_id {
+                return None;
+            }
+            node = slot.node.as_mut()?;
+        }
+        Some(node)
+    }
+
+    /// ### Assign
+    ///
+    /// Writes a slot into a map or list.
+    fn assign(target: &mut Node, key: &OpKey, slot: Slot) -> Result<(), DocumentError> {
+        match (target, key) {
+            (Node::Map(fields), OpKey::Field(key)) => match fields.get_mut(key) {
+                Some(current) => current.assign(slot),
+                None => {
+                    fields.insert(key.clone(), slot);
+                }
+            },
+            (Node::List(list), OpKey::Element(id)) => {
+                // Writes into deleted elements are discarded.
+                if let Some(current) = list.get_mut_by_id(id) {
+                    current.assign(slot);
+                }
+            }
+            _ => return Err(DocumentError::TypeMismatch),
+        }
+        Ok(())
+    }
+}
+
+#[wasm_bindgen]
+impl Document {
+    /// ### New document (JS)
+    ///
+    /// Creates a new empty document.
+    #[wasm_bindgen(constructor)]
+    pub fn new_js() -> Document {
+        Self::default()
+    }
+
+    /// ### From JS (JS)
+    ///
+    /// Creates a document from a plain JS object.
+    ///
+    /// #### Errors
+    ///
+    /// A JS exception is thrown if the value is not a plain object.
+    #[wasm_bindgen(js_name = fromJS)]
+    pub fn from_js_js(value: &JsValue, nid: UID, ts: Timestamp) -> Document {
+        Self::from_js(value, nid, ts).unwrap_throw()
+    }
+
+    /// ### To JS (JS)
+    ///
+    /// Returns the document as a plain JS object.
+    #[wasm_bindgen(js_name = toJS)]
+    pub fn to_js_js(&self) -> JsValue {
+        self.to_js()
+    }
+
+    /// ### Set (JS)
+    ///
+    /// Writes a plain JS value at a path (array of keys and indices) and returns the encoded
+    /// operation.
+    ///
+    /// #### Errors
+    ///
+    /// A JS exception is thrown if the path or the value are not valid.
+    #[wasm_bindgen(js_name = set)]
+    pub fn set_js(&mut self, path: &JsValue, value: &JsValue, nid: UID, ts: Timestamp) -> Vec<u8> {
+        let path = path_from_js(path).unwrap_throw();
+        let value = Value::from_js(value).unwrap_throw();
+        self.set(&path, value, nid, ts)
+            .unwrap_throw()
+            .serialize_var()
+    }
+
+    /// ### Delete (JS)
+    ///
+    /// Deletes the value at a path (array of keys and indices) and returns the encoded
+    /// operation.
+    ///
+    /// #### Errors
+    ///
+    /// A JS exception is thrown if the path is not valid.
+    #[wasm_bindgen(js_name = delete)]
+    pub fn delete_js(&mut self, path: &JsValue, nid: UID, ts: Timestamp) -> Vec<u8> {
+        let path = path_from_js(path).unwrap_throw();
+        self.delete(&path, nid, ts).unwrap_throw().serialize_var()
+    }
+
+    /// ### Apply operation (JS)
+    ///
+    /// Applies an encoded operation.
+    ///
+    /// #### Errors
+    ///
+    /// A JS exception is thrown if the operation cannot be decoded or applied.
+    #[wasm_bindgen(js_name = applyOp)]
+    pub fn apply_js(&mut self, encoded: &[u8]) {
+        let (op, _) = DocOp::deserialize_var(encoded).unwrap_throw();
+        self.apply(&op).unwrap_throw()
+    }
+
+    /// ### Serialize
+    ///
+    /// Returns the document (its operation log) in binary format.
+    #[wasm_bindgen(js_name = serialize)]
+    pub fn serialize_js(&self) -> Vec<u8> {
+        self.serialize_var()
+    }
+
+    /// ### Deserialize
+    ///
+    /// Constructs a document from an encoded version.
+    ///
+    /// #### Errors
+    ///
+    /// A JS exception is thrown if the encoded document is not valid.
+    #[wasm_bindgen(js_name = deserialize)]
+    pub fn deserialize_js(encoded: &[u8]) -> Document {
+        Document::deserialize_var(encoded).unwrap_throw().0
+    }
+}
+
+/// ## Path from JS
+///
+/// Converts a JS array of strings (keys) and numbers (indices) into a path.
+fn path_from_js(path: &JsValue) -> Result<Vec<PathSegment>, DocumentError> {
+    if !js_sys::Array::is_array(path) {
+        return Err(DocumentError::InvalidPath);
+    }
+    js_sys::Array::from(path)
+        .iter()
+        .map(|segment| match (segment.as_string(), segment.as_f64()) {
+            (Some(key), _) => Ok(PathSegment::Key(key)),
+            (_, Some(index)) if index >= 0.0 => Ok(PathSegment::Index(index as usize)),
+            _ => Err(DocumentError::InvalidPath),
+        })
+        .collect()
+}
+
+//#region Serialization
+impl VarSerialize for Document {
+    fn serialize_var(&self) -> Vec<u8> {
+        self.log.serialize_var()
+    }
+}
+
+impl VarDeserialize for Document {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (log, rest) = Vec::<DocOp>::deserialize_var(encoded)?;
+        let mut doc = Self::new();
+        for op in &log {
+            doc.apply(op).map_err(|_| DeserializeError::InvalidValue)?;
+        }
+        Ok((doc, rest))
+    }
+}
+//#endregion
+
+//#region DocumentError
+/// ## Document error
+///
+/// Custom error related to document operations.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DocumentError {
+    InvalidPath,
+    InvalidValue,
+    TypeMismatch,
+    UnknownElement,
+}
+//#endregion
+
+#[cfg(test)]
+mod document_tests {
+    use super::*;
+    use crate::serialization::test_var_serialization;
+    use crate::time::hlc::{HybridLogicalClock, SysTimeHLC};
+
+    fn object(fields: &[(&str, Value)]) -> Value {
+        Value::Map(
+            fields
+                .iter()
+                .map(|(key, value)| (String::from(*key), value.clone()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn path_based_updates_work() {
+        let mut hlc = SysTimeHLC::default();
+        let nid = UID::new();
+        let mut doc = Document::new();
+
+        let item = object(&[("title", "Milk".into()), ("done", false.into())]);
+        doc.set(
+            &["todos".into()],
+            Value::List(vec![item]),
+            nid,
+            hlc.generate_timestamp(),
+        )
+        .unwrap();
+        doc.set(
+            &["todos".into(), 0.into(), "done".into()],
+            true.into(),
+            nid,
+            hlc.generate_timestamp(),
+        )
+        .unwrap();
+        doc.insert(
+            &["todos".into(), 1.into()],
+            "Eggs".into(),
+            nid,
+            hlc.generate_timestamp(),
+        )
+        .unwrap();
+        doc.set(
+            &["votes".into()],
+            Value::Counter(0),
+            nid,
+            hlc.generate_timestamp(),
+        )
+        .unwrap();
+        doc.increment(&["votes".into()], -2, nid, hlc.generate_timestamp())
+            .unwrap();
+
+        assert_eq!(
+            doc.get(&["todos".into(), 0.into(), "done".into()]),
+            Some(Value::Bool(true))
+        );
+        assert_eq!(doc.get(&["todos".into(), 1.into()]), Some("Eggs".into()));
+        assert_eq!(doc.get(&["votes".into()]), Some(Value::Counter(-2)));
+
+        doc.delete(&["todos".into(), 0.into()], nid, hlc.generate_timestamp())
+            .unwrap();
+        doc.delete(&["votes".into()], nid, hlc.generate_timestamp())
+            .unwrap();
+        assert_eq!(
+            doc.to_value(),
+            object(&[("todos", Value::List(vec!["Eggs".into()]))])
+        );
+        assert_eq!(
+            doc.increment(&["todos".into()], 1, nid, hlc.generate_timestamp()),
+            Err(DocumentError::TypeMismatch)
+        );
+    }
+
+    #[test]
+    fn updates_to_replaced_values_are_discarded() {
+        let mut hlc = SysTimeHLC::default();
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut doc_a = Document::new();
+        doc_a
+            .set(&["a".into()], object(&[]), nid_a, hlc.generate_timestamp())
+            .unwrap();
+        let mut doc_b = doc_a.clone();
+
+        let nested = doc_a
+            .set(
+                &["a".into(), "b".into()],
+                1.0.into(),
+                nid_a,
+                hlc.generate_timestamp(),
+            )
+            .unwrap();
+        let replace = doc_b
+            .set(
+                &["a".into()],
+                "replaced".into(),
+                nid_b,
+                hlc.generate_timestamp(),
+            )
+            .unwrap();
+
+        doc_a.apply(&replace).unwrap();
+        doc_b.apply(&nested).unwrap();
+        doc_b.apply(&nested).unwrap();
+
+        assert_eq!(doc_a.to_value(), object(&[("a", "replaced".into())]));
+        assert_eq!(
+            doc_a.to_value(),
+            doc_b.to_value(),
+            "Replicas should converge."
+        );
+    }
+
+    #[test]
+    fn concurrent_counter_updates_are_kept() {
+        let mut hlc = SysTimeHLC::default();
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut doc_a = Document::new();
+        doc_a
+            .set(
+                &["n".into()],
+                Value::Counter(1),
+                nid_a,
+                hlc.generate_timestamp(),
+            )
+            .unwrap();
+        let mut doc_b = doc_a.clone();
+
+        doc_a
+            .increment(&["n".into()], 2, nid_a, hlc.generate_timestamp())
+            .unwrap();
+        doc_b
+            .increment(&["n".into()], 3, nid_b, hlc.generate_timestamp())
+            .unwrap();
+        doc_a.merge(&doc_b).unwrap();
+        doc_b.merge(&doc_a).unwrap();
+
+        assert_eq!(doc_a.get(&["n".into()]), Some(Value::Counter(6)));
+        assert_eq!(
+            doc_a.to_value(),
+            doc_b.to_value(),
+            "Replicas should converge."
+        );
+    }
+
+    #[test]
+    fn nested_ids_do_not_depend_on_the_replica() {
+        let mut hlc = SysTimeHLC::default();
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut doc_a = Document::new();
+        let mut doc_b = Document::new();
+
+        let (ts_1, ts_2, ts_3) = (
+            hlc.generate_timestamp(),
+            hlc.generate_timestamp(),
+            hlc.generate_timestamp(),
+        );
+        doc_b
+            .set(&["other".into()], true.into(), nid_b, ts_3)
+            .unwrap();
+        let set = doc_a
+            .set(
+                &["todos".into()],
+                Value::List(vec![1.0.into(), 2.0.into()]),
+                nid_a,
+                ts_1,
+            )
+            .unwrap();
+        let update = doc_a
+            .set(&["todos".into(), 0.into()], 9.0.into(), nid_a, ts_2)
+            .unwrap();
+
+        // `b` has already seen a later timestamp than the operations of `a`.
+        doc_b.apply(&set).unwrap();
+        doc_b.apply(&update).unwrap();
+        assert_eq!(
+            doc_b.get(&["todos".into()]),
+            Some(Value::List(vec![9.0.into(), 2.0.into()]))
+        );
+        assert_eq!(doc_a.get(&["todos".into()]), doc_b.get(&["todos".into()]));
+    }
+
+    #[test]
+    fn leaves_resolve_like_registers() {
+        let mut hlc = SysTimeHLC::default();
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let ts = hlc.generate_timestamp();
+        let mut doc_a = Document::new();
+        let mut doc_b = Document::new();
+        let set_a = doc_a.set(&["x".into()], "a".into(), nid_a, ts).unwrap();
+        let set_b = doc_b.set(&["x".into()], "b".into(), nid_b, ts).unwrap();
+        doc_a.apply(&set_b).unwrap();
+        doc_b.apply(&set_a).unwrap();
+
+        let mut register = LWWRegister::new(ts, Scalar::String(String::from("a")));
+        let other = LWWRegister::new(ts, Scalar::String(String::from("b")));
+        register.merge(&other, nid_a, nid_b);
+        let expected = match register.get_value() {
+            Scalar::String(value) => Value::String(value),
+            _ => unreachable!(),
+        };
+        assert_eq!(doc_a.get(&["x".into()]), Some(expected));
+        assert_eq!(
+            doc_a.to_value(),
+            doc_b.to_value(),
+            "Replicas should converge."
+        );
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let mut hlc = SysTimeHLC::default();
+        let nid = UID::new();
+        let mut doc = Document::new();
+        let item = object(&[("title", "Milk".into()), ("count", Value::Counter(2))]);
+        let op = doc
+            .set(
+                &["todos".into()],
+                Value::List(vec![item, Value::Null]),
+                nid,
+                hlc.generate_timestamp(),
+            )
+            .unwrap();
+        doc.delete(&["todos".into(), 1.into()], nid, hlc.generate_timestamp())
+            .unwrap();
+
+        test_var_serialization(op);
+        test_var_serialization(doc);
+    }
+}
@@ -0,0 +1,98 @@
+//! # Dot
+//!
+//! Unique identifier of an operation, made of the HLC timestamp of the operation and the ID of the
+//! node that issued it.
+//!
+//! The sequence and tree CRDTs identify their elements by the dot of the operation that created
+//! them, and order concurrent elements by dot. Sets track causality with
+//! [`CausalDot`](crate::vclock::CausalDot)s instead, which are numbered by the clock of each node.
+//!
+//! ### Usage
+//!
+//! ```rust
+//! use crdts::dot::Dot;
+//! use crdts::time::hlc::{HybridLogicalClock, SysTimeHLC};
+//! use crdts::uid::UID;
+//!
+//! let mut hlc = SysTimeHLC::default();
+//! let nid = UID::new();
+//! let first = Dot::new(nid, hlc.generate_timestamp());
+//! let second = Dot::new(nid, hlc.generate_timestamp());
+//! assert!(first < second, "Dots are ordered by timestamp.");
+//! ```
+use crate::{
+    serialization::{Deserialize, Serialize, TS_SIZE, UID_SIZE},
+    time::timestamp::Timestamp,
+    uid::UID,
+};
+use wasm_bindgen::UnwrapThrowExt;
+
+//#region Constants
+pub const DOT_SIZE: usize = TS_SIZE + UID_SIZE;
+//#endregion
+
+//#region Dot
+/// ## Dot
+///
+/// Unique tag of an operation, given by the timestamp of the operation and the ID of the node
+/// that issued it.
+///
+/// * Dots are ordered by timestamp first. Ties are decided by the node ID.
+#[derive(Copy, Clone, Default, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Dot {
+    /// ### Timestamp
+    ///
+    /// HLC timestamp of the operation.
+    pub ts: Timestamp, // encoded: 8 bytes
+
+    /// ### Node ID
+    ///
+    /// ID of the node that issued the operation.
+    pub nid: UID, // encoded: 16 bytes
+}
+
+impl Dot {
+    /// ### New dot
+    ///
+    /// Creates a dot for an operation issued by node `nid` at time `ts`.
+    #[inline]
+    pub fn new(nid: UID, ts: Timestamp) -> Self {
+        Self { ts, nid }
+    }
+}
+
+impl Serialize<DOT_SIZE> for Dot {
+    fn serialize(&self) -> [u8; DOT_SIZE] {
+        let mut encoded = [0u8; DOT_SIZE];
+        encoded[..TS_SIZE].copy_from_slice(&self.ts.serialize());
+        encoded[TS_SIZE..].copy_from_slice(&self.nid.serialize());
+        encoded
+    }
+}
+
+impl Deserialize<DOT_SIZE> for Dot {
+    fn deserialize(encoded: [u8; DOT_SIZE]) -> Self {
+        let (ts, nid) = encoded.split_at(TS_SIZE);
+        Self {
+            ts: Timestamp::deserialize(ts.try_into().unwrap_throw()),
+            nid: UID::deserialize(nid.try_into().unwrap_throw()),
+        }
+    }
+}
+
+crate::impl_var_serialization!(Dot, DOT_SIZE);
+//#endregion
+
+#[cfg(test)]
+mod dot_tests {
+    use super::*;
+    use crate::serialization::{test_serialization, test_var_serialization};
+    use crate::time::hlc::{HybridLogicalClock, SysTimeHLC};
+
+    #[test]
+    fn serialization_deserialization_works() {
+        test_serialization::<Dot, DOT_SIZE>();
+        let dot = Dot::new(UID::new(), SysTimeHLC::default().generate_timestamp());
+        test_var_serialization(dot);
+    }
+}
@@ -0,0 +1,227 @@
+//! # Dot context
+//!
+//! Implementation of a _causal context_ (also known as dotted version vector): the set of events
+//! a replica has seen, each identified by a [`CausalDot`].
+//!
+//! The context is kept compact by storing the dots of each node that form a contiguous range
+//! from 1 in a [`VClock`], and only the dots received out of order (i.e. after a gap) in a _dot
+//! cloud_. [`DotContext::compact`] moves the dots of the cloud into the clock once the gaps before
+//! them are filled.
+//!
+//! Set CRDTs such as [`ORSet`](crate::orset::ORSet) tag their entries with dots generated by
+//! [`DotContext::next_dot`] and keep a single context to tell removed entries (whose dot is in the
+//! context) from entries they have not seen yet, without keeping tombstones.
+//!
+//! Reference: [Delta State Replicated Data Types - Almeida et. al.](https://doi.org/10.1016/j.jpdc.2017.08.003)
+//!
+//! ### Usage
+//!
+//! ```rust
+//! use crdts::dotcontext::DotContext;
+//! use crdts::uid::UID;
+//!
+//! let (nid_a, nid_b) = (UID::new(), UID::new());
+//!
+//! let mut context = DotContext::new();
+//! context.next_dot(nid_a);
+//! // Dot 2 of `b` arrives before dot 1.
+//! context.add_dot((nid_b, 2));
+//! assert!(context.contains(&(nid_b, 2)) && !context.contains(&(nid_b, 1)));
+//!
+//! context.add_dot((nid_b, 1));
+//! context.compact();
+//! assert_eq!(context.clock().get(nid_b), 2);
+//! assert!(context.cloud().next().is_none());
+//! ```
+use crate::{
+    serialization::{DeserializeError, VarDeserialize, VarSerialize},
+    uid::UID,
+    vclock::{CausalDot, VClock},
+};
+use std::collections::BTreeSet;
+
+/// ## Dot context
+///
+/// Causal context made of a version vector and a dot cloud.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DotContext {
+    /// ### Clock
+    ///
+    /// Latest dot of each node such that all the previous dots of the node have been seen.
+    clock: VClock,
+
+    /// ### Cloud
+    ///
+    /// Dots seen after a gap, i.e. not covered by the clock.
+    cloud: BTreeSet<CausalDot>,
+}
+
+impl Default for DotContext {
+    fn default() -> Self {
+        Self {
+            clock: VClock::new(None),
+            cloud: BTreeSet::new(),
+        }
+    }
+}
+
+impl DotContext {
+    /// ### New DotContext
+    ///
+    /// Creates a new empty context.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ### Clock
+    ///
+    /// Returns the compact part of the context.
+    #[inline]
+    pub fn clock(&self) -> &VClock {
+        &self.clock
+    }
+
+    /// ### Cloud
+    ///
+    /// Returns an iterator over the dots that are not covered by the clock, in ascending order.
+    #[inline]
+    pub fn cloud(&self) -> impl Iterator<Item = &CausalDot> {
+        self.cloud.iter()
+    }
+
+    /// ### Contains
+    ///
+    /// Returns whether a dot has been seen.
+    pub fn contains(&self, dot: &CausalDot) -> bool {
+        let (node_id, counter) = *dot;
+        counter <= self.clock.get(node_id) || self.cloud.contains(dot)
+    }
+
+    /// ### Next dot
+    ///
+    /// Generates a new dot for a local event and adds it to the context.
+    ///
+    /// * `node_id` - ID of the current node.
+    /// * Returns the new dot.
+    pub fn next_dot(&mut self, node_id: UID) -> CausalDot {
+        self.clock.increment(node_id);
+        self.clock.dot(node_id)
+    }
+
+    /// ### Add dot
+    ///
+    /// Adds a dot received from another replica. The dot is kept in the cloud until
+    /// [`DotContext::compact`] is called.
+    ///
+    /// * `dot` - Dot to add.
+    pub fn add_dot(&mut self, dot: CausalDot) {
+        if !self.contains(&dot) {
+            self.cloud.insert(dot);
+        }
+    }
+
+    /// ### Compact
+    ///
+    /// Moves the dots of the cloud that directly follow the clock into the clock, and drops the
+    /// ones it already covers.
+    pub fn compact(&mut self) {
+        let cloud = std::mem::take(&mut self.cloud);
+        for (node_id, counter) in cloud {
+            let current = self.clock.get(node_id);
+            if counter == current + 1 {
+                self.clock.increment(node_id);
+            } else if counter > current {
+                self.cloud.insert((node_id, counter));
+            }
+        }
+    }
+
+    /// ### Merge
+    ///
+    /// Merges another context into the current one (union of the seen dots) and compacts the
+    /// result.
+    ///
+    /// * `other` - State of another context.
+    pub fn merge(&mut self, other: &Self) {
+        self.clock.merge(&other.clock);
+        self.cloud.extend(other.cloud.iter().copied());
+        self.compact();
+    }
+}
+
+//#region Serialization
+impl VarSerialize for DotContext {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = self.clock.serialize_var();
+        encoded.extend(self.cloud.serialize_var());
+        encoded
+    }
+}
+
+impl VarDeserialize for DotContext {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (clock, rest) = VClock::deserialize_var(encoded)?;
+        let (cloud, rest) = BTreeSet::deserialize_var(rest)?;
+        Ok((Self { clock, cloud }, rest))
+    }
+}
+//#endregion
+
+#[cfg(test)]
+mod dotcontext_tests {
+    use super::*;
+    use crate::serialization::test_var_serialization;
+
+    #[test]
+    fn compact_fills_gaps() {
+        let nid = UID::new();
+        let mut context = DotContext::new();
+        context.add_dot((nid, 2));
+        context.add_dot((nid, 4));
+        context.compact();
+        assert_eq!(context.clock().get(nid), 0, "Dot 1 is still missing.");
+        assert!(!context.contains(&(nid, 3)));
+
+        context.add_dot((nid, 1));
+        context.add_dot((nid, 3));
+        context.compact();
+        assert_eq!(context.clock().get(nid), 4);
+        assert_eq!(context.cloud().count(), 0);
+        assert!(context.contains(&(nid, 3)) && !context.contains(&(nid, 5)));
+        assert_eq!(context.next_dot(nid), (nid, 5));
+    }
+
+    #[test]
+    fn merge_computes_union() {
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut context_a = DotContext::new();
+        let mut context_b = DotContext::new();
+        context_a.next_dot(nid_a);
+        context_a.add_dot((nid_b, 2));
+        context_b.next_dot(nid_b);
+        context_b.add_dot((nid_a, 3));
+
+        let mut merged_b = context_b.clone();
+        merged_b.merge(&context_a);
+        context_a.merge(&context_b);
+        context_a.merge(&context_b);
+
+        assert_eq!(
+            context_a, merged_b,
+            "Merge should be commutative and idempotent."
+        );
+        assert_eq!(context_a.clock().get(nid_b), 2);
+        assert_eq!(context_a.cloud().collect::<Vec<_>>(), vec![&(nid_a, 3)]);
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let nid = UID::new();
+        let mut context = DotContext::new();
+        context.next_dot(nid);
+        context.add_dot((UID::new(), 7));
+        test_var_serialization(context);
+        test_var_serialization(DotContext::new());
+    }
+}
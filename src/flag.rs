@@ -0,0 +1,273 @@
+//! # Flag CRDTs
+//!
+//! Implementation of the _enable-wins flag (EWFlag)_ and _disable-wins flag (DWFlag)_ CRDTs.
+//!
+//! Unlike a [`LWWRegister`](crate::lwwregister::LWWRegister) over a boolean, flags do not decide
+//! concurrent updates by timestamp. Instead, the semantics of the flag picks the winner:
+//!
+//! * An **EWFlag** is enabled as long as some enable has not been observed by a disable. A
+//!   concurrent enable and disable leave the flag enabled.
+//! * A **DWFlag** is disabled as long as some disable has not been observed by an enable. A
+//!   concurrent enable and disable leave the flag disabled.
+//!
+//! Both flags are built on an [`ORSet`]: the winning update adds a
+//! [`CausalDot`](crate::vclock::CausalDot) to the set, the losing update removes the dots observed
+//! so far.
+//!
+//! ### Usage
+//!
+//! ```rust
+//! use crdts::flag::{DWFlag, EWFlag};
+//! use crdts::uid::UID;
+//!
+//! let (nid_a, nid_b) = (UID::new(), UID::new());
+//!
+//! let mut flag_a = EWFlag::new();
+//! let mut flag_b = EWFlag::new();
+//! flag_a.enable(nid_a);
+//! flag_b.disable();
+//! flag_b.merge(&flag_a);
+//! assert!(flag_b.is_enabled(), "Enable wins over a concurrent disable.");
+//!
+//! let mut flag_a = DWFlag::new();
+//! let mut flag_b = DWFlag::new();
+//! flag_a.disable(nid_a);
+//! flag_b.enable();
+//! flag_b.merge(&flag_a);
+//! assert!(!flag_b.is_enabled(), "Disable wins over a concurrent enable.");
+//! ```
+use crate::{
+    orset::ORSet,
+    serialization::{DeserializeError, VarDeserialize, VarSerialize},
+    uid::UID,
+};
+
+//#region EWFlag
+/// ## Enable-wins flag
+///
+/// Boolean flag where enables win over concurrent disables. A new flag is disabled.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EWFlag {
+    /// ### Enables
+    ///
+    /// Enables not yet observed by a disable.
+    enables: ORSet<bool>,
+}
+
+impl EWFlag {
+    /// ### New EWFlag
+    ///
+    /// Creates a new disabled flag.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ### Is enabled
+    ///
+    /// Returns whether the flag is enabled.
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.enables.contains(&true)
+    }
+
+    /// ### Enable
+    ///
+    /// Enables the flag.
+    ///
+    /// * `nid` - ID of the current node.
+    #[inline]
+    pub fn enable(&mut self, nid: UID) {
+        self.enables.add(true, nid);
+    }
+
+    /// ### Disable
+    ///
+    /// Disables the flag, discarding the enables observed so far.
+    #[inline]
+    pub fn disable(&mut self) {
+        self.enables.remove(&true);
+    }
+
+    /// ### Set
+    ///
+    /// Enables or disables the flag.
+    pub fn set(&mut self, value: bool, nid: UID) {
+        if value {
+            self.enable(nid);
+        } else {
+            self.disable();
+        }
+    }
+
+    /// ### Merge
+    ///
+    /// Merges the state of another flag into the current one.
+    ///
+    /// * `other` - State of another flag.
+    #[inline]
+    pub fn merge(&mut self, other: &Self) {
+        self.enables.merge(&other.enables);
+    }
+}
+
+impl VarSerialize for EWFlag {
+    fn serialize_var(&self) -> Vec<u8> {
+        self.enables.serialize_var()
+    }
+}
+
+impl VarDeserialize for EWFlag {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (enables, rest) = ORSet::deserialize_var(encoded)?;
+        Ok((Self { enables }, rest))
+    }
+}
+//#endregion
+
+//#region DWFlag
+/// ## Disable-wins flag
+///
+/// Boolean flag where disables win over concurrent enables. A new flag is enabled.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DWFlag {
+    /// ### Disables
+    ///
+    /// Disables not yet observed by an enable.
+    disables: ORSet<bool>,
+}
+
+impl DWFlag {
+    /// ### New DWFlag
+    ///
+    /// Creates a new enabled flag.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ### Is enabled
+    ///
+    /// Returns whether the flag is enabled.
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        !self.disables.contains(&false)
+    }
+
+    /// ### Enable
+    ///
+    /// Enables the flag, discarding the disables observed so far.
+    #[inline]
+    pub fn enable(&mut self) {
+        self.disables.remove(&false);
+    }
+
+    /// ### Disable
+    ///
+    /// Disables the flag.
+    ///
+    /// * `nid` - ID of the current node.
+    #[inline]
+    pub fn disable(&mut self, nid: UID) {
+        self.disables.add(false, nid);
+    }
+
+    /// ### Set
+    ///
+    /// Enables or disables the flag.
+    pub fn set(&mut self, value: bool, nid: UID) {
+        if value {
+            self.enable();
+        } else {
+            self.disable(nid);
+        }
+    }
+
+    /// ### Merge
+    ///
+    /// Merges the state of another flag into the current one.
+    ///
+    /// * `other` - State of another flag.
+    #[inline]
+    pub fn merge(&mut self, other: &Self) {
+        self.disables.merge(&other.disables);
+    }
+}
+
+impl VarSerialize for DWFlag {
+    fn serialize_var(&self) -> Vec<u8> {
+        self.disables.serialize_var()
+    }
+}
+
+impl VarDeserialize for DWFlag {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (disables, rest) = ORSet::deserialize_var(encoded)?;
+        Ok((Self { disables }, rest))
+    }
+}
+//#endregion
+
+#[cfg(test)]
+mod flag_tests {
+    use super::*;
+    use crate::serialization::test_var_serialization;
+
+    #[test]
+    fn ewflag_enable_wins() {
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut flag_a = EWFlag::new();
+        flag_a.enable(nid_a);
+        let mut flag_b = flag_a.clone();
+
+        // The disable is issued later, but is concurrent to the enable.
+        flag_a.enable(nid_a);
+        flag_b.disable();
+        assert!(!flag_b.is_enabled());
+
+        let mut merged_b = flag_b.clone();
+        merged_b.merge(&flag_a);
+        flag_a.merge(&flag_b);
+        assert!(flag_a.is_enabled(), "Enable should win.");
+        assert_eq!(flag_a, merged_b, "Merge should be commutative.");
+
+        flag_a.disable();
+        merged_b.merge(&flag_a);
+        assert!(
+            !merged_b.is_enabled(),
+            "Observed enables should be disabled."
+        );
+    }
+
+    #[test]
+    fn dwflag_disable_wins() {
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut flag_a = DWFlag::new();
+        assert!(flag_a.is_enabled());
+        flag_a.disable(nid_a);
+        let mut flag_b = flag_a.clone();
+
+        flag_a.disable(nid_a);
+        flag_b.enable();
+        assert!(flag_b.is_enabled());
+
+        let mut merged_b = flag_b.clone();
+        merged_b.merge(&flag_a);
+        flag_a.merge(&flag_b);
+        assert!(!flag_a.is_enabled(), "Disable should win.");
+        assert_eq!(flag_a, merged_b, "Merge should be commutative.");
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let nid = UID::new();
+        let mut ew_flag = EWFlag::new();
+        ew_flag.enable(nid);
+        ew_flag.disable();
+        ew_flag.enable(nid);
+        let mut dw_flag = DWFlag::new();
+        dw_flag.disable(nid);
+        test_var_serialization(ew_flag);
+        test_var_serialization(dw_flag);
+    }
+}
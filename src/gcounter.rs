@@ -1,6 +1,26 @@
 //! # GCounter CRDT
 //!
 //! Implementation of a _grow-only-counter_ CRDT.
+//!
+//! ### Delta-state replication
+//!
+//! Mutators return a _delta_: a counter holding only the entry of the node that changed. Deltas
+//! are joined with [`GCounter::merge_delta`] and can be applied more than once or out of order,
+//! so replicas can exchange deltas after every change and only merge the full state (with
+//! [`GCounter::merge_from_state`]) when reconnecting.
+//!
+//! Reference: [Delta State Replicated Data Types - Almeida et. al.](https://doi.org/10.1016/j.jpdc.2017.08.003)
+//!
+//! ### Serialization
+//!
+//! Nodes are identified by their [`UID`]. A counter is encoded compactly as the number of entries
+//! followed by the entries themselves, each being the encoded node ID and its count. The number of
+//! entries and the counts are varints (see [`encode_varint`]), so small counts take a single
+//! byte.
+use crate::{
+    serialization::{decode_varint, encode_varint, DeserializeError, VarDeserialize, VarSerialize},
+    uid::UID,
+};
 use std::cmp::max;
 use std::collections::BTreeMap;
 
@@ -12,7 +32,7 @@ pub struct GCounter {
     /// ### Node counts
     ///
     ///  Map tracking the counts of each node in the system.
-    pub state: BTreeMap<String, u32>,
+    pub state: BTreeMap<UID, u64>,
 }
 
 impl GCounter {
@@ -21,26 +41,55 @@ impl GCounter {
     /// Creates a new GCounter.
     ///
     /// * `node_id` - If given, initializes the count for the current node (as 0).
-    pub fn new(node_id: Option<&String>) -> Self {
+    pub fn new(node_id: Option<UID>) -> Self {
         let mut counts = BTreeMap::new();
-        if node_id.is_some() {
-            counts.insert(String::from(node_id.unwrap()), 0);
+        if let Some(node_id) = node_id {
+            counts.insert(node_id, 0);
         }
         Self { state: counts }
     }
 
     /// ### Get GCounter Value
     ///
-    /// Returns the total count among all nodes.
-    pub fn get_value(&self) -> u32 {
-        self.state.values().sum()
+    /// Returns the total count among all nodes, saturating at [`u64::MAX`].
+    pub fn get_value(&self) -> u64 {
+        self.state
+            .values()
+            .fold(0, |total, count| total.saturating_add(*count))
     }
 
     /// ### Increment Counter
     ///
     /// Increments the counter by 1.
-    pub fn increment(&mut self, node_id: &String) -> () {
-        *self.state.entry(node_id.clone()).or_insert(0) += 1;
+    ///
+    /// * Returns the delta of the operation.
+    pub fn increment(&mut self, node_id: UID) -> Self {
+        self.increment_by(node_id, 1)
+    }
+
+    /// ### Increment Counter by amount
+    ///
+    /// Increments the counter by an arbitrary amount.
+    ///
+    /// * `node_id` - ID of the current node.
+    /// * `amount` - Amount to add.
+    /// * Returns the delta of the operation.
+    pub fn increment_by(&mut self, node_id: UID, amount: u64) -> Self {
+        let count = self.state.entry(node_id).or_insert(0);
+        *count = count.saturating_add(amount);
+        Self {
+            state: BTreeMap::from([(node_id, *count)]),
+        }
+    }
+
+    /// ### Merge delta
+    ///
+    /// Joins a delta returned by a mutator of another replica into the counter.
+    ///
+    /// * `delta` - Delta to join.
+    #[inline]
+    pub fn merge_delta(&mut self, delta: &Self) {
+        self.merge_from_state(delta);
     }
 
     /// ### Merge from state
@@ -51,8 +100,8 @@ impl GCounter {
     pub fn merge_from_state(&mut self, other_counter: &Self) {
         // Update partial counts
         for (node_id, partial_count) in &other_counter.state {
-            let new_partial_count: u32;
-            match self.state.get(&node_id.to_string()) {
+            let new_partial_count: u64;
+            match self.state.get(node_id) {
                 Some(curr_val) => {
                     // Both counters have a partial count for the same node. Take the largest one.
                     new_partial_count = max(*curr_val, *partial_count);
@@ -63,7 +112,83 @@ impl GCounter {
                     new_partial_count = *partial_count;
                 }
             }
-            self.state.insert(node_id.clone(), new_partial_count);
+            self.state.insert(*node_id, new_partial_count);
         }
     }
 }
+
+//#region Serialization
+impl VarSerialize for GCounter {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = encode_varint(self.state.len() as u64);
+        for (node_id, count) in &self.state {
+            encoded.extend(node_id.serialize_var());
+            encoded.extend(encode_varint(*count));
+        }
+        encoded
+    }
+}
+
+impl VarDeserialize for GCounter {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (len, mut rest) = decode_varint(encoded)?;
+        let mut state = BTreeMap::new();
+        for _ in 0..len {
+            let (node_id, next) = UID::deserialize_var(rest)?;
+            let (count, next) = decode_varint(next)?;
+            state.insert(node_id, count);
+            rest = next;
+        }
+        Ok((Self { state }, rest))
+    }
+}
+//#endregion
+
+#[cfg(test)]
+mod gcounter_tests {
+    use super::*;
+    use crate::serialization::test_var_serialization;
+
+    #[test]
+    fn deltas_converge_to_full_state() {
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut counter_a = GCounter::new(None);
+        let mut counter_b = GCounter::new(None);
+        counter_b.increment_by(nid_b, 5);
+        let mut full_b = counter_b.clone();
+
+        let deltas = [counter_a.increment(nid_a), counter_a.increment_by(nid_a, 3)];
+        assert_eq!(
+            deltas[1].state.len(),
+            1,
+            "A delta only holds the changed entry."
+        );
+
+        // Deltas can be delivered out of order and more than once.
+        for delta in deltas.iter().rev().chain(deltas.iter()) {
+            counter_b.merge_delta(delta);
+        }
+        full_b.merge_from_state(&counter_a);
+        assert_eq!(counter_b, full_b);
+        assert_eq!(counter_b.get_value(), 9);
+    }
+
+    #[test]
+    fn counts_saturate() {
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut counter = GCounter::new(None);
+        counter.increment_by(nid_a, u64::MAX - 1);
+        counter.increment_by(nid_a, 2);
+        assert_eq!(counter.state.get(&nid_a), Some(&u64::MAX));
+
+        counter.increment(nid_b);
+        assert_eq!(counter.get_value(), u64::MAX);
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let mut counter = GCounter::new(Some(UID::new()));
+        counter.increment_by(UID::new(), 2);
+        test_var_serialization(counter);
+    }
+}
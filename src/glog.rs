@@ -0,0 +1,282 @@
+//! # GLog CRDT
+//!
+//! Implementation of a _grow-only log_: an append-only sequence of entries (e.g. an activity
+//! feed), each identified by the [`Timestamp`] at which it was appended and the [`UID`] of the
+//! node that appended it.
+//!
+//! Entries are iterated in the total order of their IDs, i.e. by timestamp first and node ID
+//! second. Since the timestamps are generated by hybrid logical clocks, this order respects
+//! causality. Merging two logs computes the union of their entries.
+//!
+//! ### Synchronization
+//!
+//! A replica summarizes which entries it has with the latest timestamp it has seen from each
+//! node ([`GLog::summary`]). Given such a summary, another replica computes the entries the first
+//! one is missing ([`GLog::missing`]). This requires that the entries of each node are always
+//! shared in order, which holds as long as logs are only exchanged through
+//! [`GLog::missing`] and [`GLog::merge`].
+//!
+//! ### Usage
+//!
+//! ```rust
+//! use crdts::glog::GLog;
+//! use crdts::time::hlc::{HybridLogicalClock, SysTimeHLC};
+//! use crdts::uid::UID;
+//!
+//! let mut hlc = SysTimeHLC::default();
+//! let (nid_a, nid_b) = (UID::new(), UID::new());
+//!
+//! let mut log_a = GLog::new();
+//! let mut log_b = GLog::new();
+//! log_a.append(nid_a, hlc.generate_timestamp(), b"joined".to_vec());
+//! log_b.append(nid_b, hlc.generate_timestamp(), b"left".to_vec());
+//!
+//! let missing = log_a.missing(&log_b.summary());
+//! log_b.extend(missing);
+//! log_a.merge(&log_b);
+//! assert_eq!(log_a, log_b);
+//! ```
+use crate::{
+    serialization::{DeserializeError, VarDeserialize, VarSerialize},
+    time::timestamp::Timestamp,
+    uid::UID,
+};
+use std::cmp::max;
+use std::collections::BTreeMap;
+
+/// ## Entry ID
+///
+/// Unique ID of a log entry: the timestamp at which it was appended and the ID of its node.
+pub type EntryId = (Timestamp, UID);
+
+/// ## GLog
+///
+/// Append-only log of binary payloads.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GLog {
+    /// ### Entries
+    ///
+    /// Payloads of all entries, by entry ID.
+    entries: BTreeMap<EntryId, Vec<u8>>,
+}
+
+impl GLog {
+    /// ### New GLog
+    ///
+    /// Creates a new empty log.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ### Append
+    ///
+    /// Appends an entry to the log.
+    ///
+    /// * `nid` - ID of the current node.
+    /// * `ts` - Timestamp of the entry. If the node already appended an entry with the same or a
+    ///   later timestamp, the entry gets a timestamp right after that one instead.
+    /// * `payload` - Content of the entry.
+    /// * Returns the ID of the new entry.
+    pub fn append(&mut self, nid: UID, ts: Timestamp, payload: Vec<u8>) -> EntryId {
+        // The last entry of the node is usually among the last entries of the log.
+        let last_ts = self
+            .entries
+            .keys()
+            .rev()
+            .find(|(_, entry_nid)| *entry_nid == nid)
+            .map(|(entry_ts, _)| *entry_ts);
+        let ts = match last_ts {
+            Some(mut next_ts) => {
+                next_ts.increase_counter();
+                max(ts, next_ts)
+            }
+            None => ts,
+        };
+        self.entries.insert((ts, nid), payload);
+        (ts, nid)
+    }
+
+    /// ### Insert
+    ///
+    /// Adds an entry received from another replica. Entries that are already in the log are
+    /// ignored.
+    ///
+    /// * Returns whether the entry was new.
+    pub fn insert(&mut self, id: EntryId, payload: Vec<u8>) -> bool {
+        if self.entries.contains_key(&id) {
+            return false;
+        }
+        self.entries.insert(id, payload);
+        true
+    }
+
+    /// ### Extend
+    ///
+    /// Adds several entries received from another replica.
+    pub fn extend(&mut self, entries: impl IntoIterator<Item = (EntryId, Vec<u8>)>) {
+        for (id, payload) in entries {
+            self.insert(id, payload);
+        }
+    }
+
+    /// ### Get
+    ///
+    /// Returns the payload of an entry.
+    #[inline]
+    pub fn get(&self, id: &EntryId) -> Option<&[u8]> {
+        self.entries.get(id).map(Vec::as_slice)
+    }
+
+    /// ### Iterate
+    ///
+    /// Returns an iterator over all entries, in total order.
+    pub fn iter(&self) -> impl Iterator<Item = (&EntryId, &[u8])> {
+        self.entries
+            .iter()
+            .map(|(id, payload)| (id, payload.as_slice()))
+    }
+
+    /// ### Since
+    ///
+    /// Returns an iterator over the entries appended strictly after a given timestamp, in total
+    /// order.
+    pub fn since(&self, ts: Timestamp) -> impl Iterator<Item = (&EntryId, &[u8])> {
+        self.iter()
+            .skip_while(move |((entry_ts, _), _)| *entry_ts <= ts)
+    }
+
+    /// ### Length
+    ///
+    /// Returns the number of entries in the log.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// ### Is empty
+    ///
+    /// Returns whether the log has no entries.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// ### Summary
+    ///
+    /// Returns the latest timestamp of the entries of each node.
+    pub fn summary(&self) -> BTreeMap<UID, Timestamp> {
+        let mut summary = BTreeMap::new();
+        for (ts, nid) in self.entries.keys() {
+            summary.insert(*nid, *ts);
+        }
+        summary
+    }
+
+    /// ### Missing
+    ///
+    /// Returns the entries another replica is missing, in total order.
+    ///
+    /// * `summary` - Summary of the other replica (see [`GLog::summary`]).
+    pub fn missing(&self, summary: &BTreeMap<UID, Timestamp>) -> Vec<(EntryId, Vec<u8>)> {
+        self.entries
+            .iter()
+            .filter(|((ts, nid), _)| summary.get(nid).is_none_or(|last_ts| ts > last_ts))
+            .map(|(id, payload)| (*id, payload.clone()))
+            .collect()
+    }
+
+    /// ### Merge
+    ///
+    /// Merges another log into the current one (union of the entries).
+    ///
+    /// * `other` - State of another log.
+    pub fn merge(&mut self, other: &Self) {
+        for (id, payload) in &other.entries {
+            self.entries.entry(*id).or_insert_with(|| payload.clone());
+        }
+    }
+}
+
+//#region Serialization
+impl VarSerialize for GLog {
+    fn serialize_var(&self) -> Vec<u8> {
+        self.entries.serialize_var()
+    }
+}
+
+impl VarDeserialize for GLog {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (entries, rest) = BTreeMap::deserialize_var(encoded)?;
+        Ok((Self { entries }, rest))
+    }
+}
+//#endregion
+
+#[cfg(test)]
+mod glog_tests {
+    use super::*;
+    use crate::serialization::test_var_serialization;
+    use crate::time::hlc::{HybridLogicalClock, SysTimeHLC};
+
+    #[test]
+    fn entries_are_totally_ordered() {
+        let mut hlc = SysTimeHLC::default();
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let ts = hlc.generate_timestamp();
+
+        let mut log = GLog::new();
+        let first = log.append(nid_a, ts, vec![1]);
+        let second = log.append(nid_a, ts, vec![2]);
+        let concurrent = (ts, nid_b);
+        assert!(log.insert(concurrent, vec![3]));
+        assert!(
+            !log.insert(concurrent, vec![4]),
+            "Entries are never overwritten."
+        );
+
+        assert!(first < second, "Local entries get increasing timestamps.");
+        let mut expected = vec![(first, vec![1]), (second, vec![2]), (concurrent, vec![3])];
+        expected.sort();
+        let entries: Vec<_> = log.iter().map(|(id, p)| (*id, p.to_vec())).collect();
+        assert_eq!(entries, expected);
+        assert_eq!(
+            log.since(ts).map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![second]
+        );
+    }
+
+    #[test]
+    fn missing_entries_synchronize_replicas() {
+        let mut hlc = SysTimeHLC::default();
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut log_a = GLog::new();
+        let mut log_b = GLog::new();
+
+        log_a.append(nid_a, hlc.generate_timestamp(), vec![1]);
+        log_b.merge(&log_a);
+        log_a.append(nid_a, hlc.generate_timestamp(), vec![2]);
+        log_b.append(nid_b, hlc.generate_timestamp(), vec![3]);
+
+        let missing_b = log_a.missing(&log_b.summary());
+        assert_eq!(missing_b.len(), 1);
+        let missing_a = log_b.missing(&log_a.summary());
+        assert_eq!(missing_a.len(), 1);
+
+        log_b.extend(missing_b);
+        log_a.extend(missing_a);
+        assert_eq!(log_a, log_b, "Replicas should converge.");
+        assert!(log_a.missing(&log_b.summary()).is_empty());
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let mut hlc = SysTimeHLC::default();
+        let nid = UID::new();
+        let mut log = GLog::new();
+        log.append(nid, hlc.generate_timestamp(), b"a".to_vec());
+        log.append(nid, hlc.generate_timestamp(), Vec::new());
+        test_var_serialization(log);
+        test_var_serialization(GLog::new());
+    }
+}
@@ -0,0 +1,280 @@
+//! # Graph CRDT
+//!
+//! Implementation of an _add-wins directed graph_ CRDT, made of two [`ORSet`]s: one for the
+//! vertices and one for the edges.
+//!
+//! An edge is only visible while both of its endpoints are in the graph. Removing a vertex
+//! removes the edges observed at that moment, and edges added concurrently to the removal stay
+//! hidden (until the vertex is added again), so all replicas agree on the visible edges after
+//! merging, whatever the order of the operations. As with the underlying sets, a vertex added
+//! concurrently to its removal stays in the graph.
+//!
+//! ### Usage
+//!
+//! ```rust
+//! use crdts::graph::Graph;
+//! use crdts::uid::UID;
+//!
+//! let (nid_a, nid_b) = (UID::new(), UID::new());
+//!
+//! let mut graph_a = Graph::new();
+//! graph_a.add_vertex("note", nid_a);
+//! graph_a.add_vertex("page", nid_a);
+//! let mut graph_b = graph_a.clone();
+//!
+//! // Concurrent link on `a` and removal of its target on `b`.
+//! graph_a.add_edge("note", "page", nid_a).unwrap();
+//! graph_b.remove_vertex(&"page");
+//!
+//! graph_a.merge(&graph_b);
+//! assert_eq!(graph_a.neighbors(&"note").count(), 0, "Dangling edges are hidden.");
+//! ```
+use crate::{
+    orset::ORSet,
+    serialization::{DeserializeError, VarDeserialize, VarSerialize},
+    uid::UID,
+};
+
+/// ## Graph
+///
+/// Add-wins directed graph over a generic vertex type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Graph<V: Ord + Clone> {
+    /// ### Vertices
+    ///
+    /// Vertices of the graph.
+    vertices: ORSet<V>,
+
+    /// ### Edges
+    ///
+    /// Edges of the graph as (source, target) pairs, including dangling ones.
+    edges: ORSet<(V, V)>,
+}
+
+impl<V: Ord + Clone> Default for Graph<V> {
+    fn default() -> Self {
+        Self {
+            vertices: ORSet::new(),
+            edges: ORSet::new(),
+        }
+    }
+}
+
+impl<V: Ord + Clone> Graph<V> {
+    /// ### New Graph
+    ///
+    /// Creates a new empty graph.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ### Add vertex
+    ///
+    /// Adds a vertex to the graph.
+    ///
+    /// * `vertex` - Vertex to add.
+    /// * `nid` - ID of the current node.
+    #[inline]
+    pub fn add_vertex(&mut self, vertex: V, nid: UID) {
+        self.vertices.add(vertex, nid);
+    }
+
+    /// ### Remove vertex
+    ///
+    /// Removes a vertex from the graph, together with its edges.
+    ///
+    /// * `vertex` - Vertex to remove.
+    pub fn remove_vertex(&mut self, vertex: &V) {
+        let incident: Vec<(V, V)> = self
+            .edges
+            .iter()
+            .filter(|(source, target)| source == vertex || target == vertex)
+            .cloned()
+            .collect();
+        for edge in &incident {
+            self.edges.remove(edge);
+        }
+        self.vertices.remove(vertex);
+    }
+
+    /// ### Add edge
+    ///
+    /// Adds an edge from `source` to `target`.
+    ///
+    /// * `nid` - ID of the current node.
+    /// * Throws [`GraphError::UnknownVertex`] if any of the endpoints is not in the graph.
+    pub fn add_edge(&mut self, source: V, target: V, nid: UID) -> Result<(), GraphError> {
+        if !self.contains_vertex(&source) || !self.contains_vertex(&target) {
+            return Err(GraphError::UnknownVertex);
+        }
+        self.edges.add((source, target), nid);
+        Ok(())
+    }
+
+    /// ### Remove edge
+    ///
+    /// Removes the edge from `source` to `target`.
+    #[inline]
+    pub fn remove_edge(&mut self, source: &V, target: &V) {
+        self.edges.remove(&(source.clone(), target.clone()));
+    }
+
+    /// ### Contains vertex
+    ///
+    /// Returns whether a vertex is in the graph.
+    #[inline]
+    pub fn contains_vertex(&self, vertex: &V) -> bool {
+        self.vertices.contains(vertex)
+    }
+
+    /// ### Contains edge
+    ///
+    /// Returns whether the edge from `source` to `target` is visible.
+    pub fn contains_edge(&self, source: &V, target: &V) -> bool {
+        self.contains_vertex(source)
+            && self.contains_vertex(target)
+            && self.edges.contains(&(source.clone(), target.clone()))
+    }
+
+    /// ### Vertices
+    ///
+    /// Returns an iterator over the vertices of the graph, in ascending order.
+    #[inline]
+    pub fn vertices(&self) -> impl Iterator<Item = &V> {
+        self.vertices.iter()
+    }
+
+    /// ### Edges
+    ///
+    /// Returns an iterator over the visible edges of the graph as (source, target) pairs, in
+    /// ascending order.
+    pub fn edges(&self) -> impl Iterator<Item = (&V, &V)> {
+        self.edges
+            .iter()
+            .filter(|(source, target)| self.contains_vertex(source) && self.contains_vertex(target))
+            .map(|(source, target)| (source, target))
+    }
+
+    /// ### Neighbors
+    ///
+    /// Returns an iterator over the targets of the visible edges leaving a vertex.
+    pub fn neighbors<'a>(&'a self, vertex: &'a V) -> impl Iterator<Item = &'a V> {
+        self.edges()
+            .filter(move |(source, _)| *source == vertex)
+            .map(|(_, target)| target)
+    }
+
+    /// ### Backlinks
+    ///
+    /// Returns an iterator over the sources of the visible edges reaching a vertex.
+    pub fn backlinks<'a>(&'a self, vertex: &'a V) -> impl Iterator<Item = &'a V> {
+        self.edges()
+            .filter(move |(_, target)| *target == vertex)
+            .map(|(source, _)| source)
+    }
+
+    /// ### Merge
+    ///
+    /// Merges the state of another graph into the current one.
+    ///
+    /// * `other` - State of another graph.
+    pub fn merge(&mut self, other: &Self) {
+        self.vertices.merge(&other.vertices);
+        self.edges.merge(&other.edges);
+    }
+}
+
+//#region Serialization
+impl<V: Ord + Clone + VarSerialize> VarSerialize for Graph<V> {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = self.vertices.serialize_var();
+        encoded.extend(self.edges.serialize_var());
+        encoded
+    }
+}
+
+impl<V: Ord + Clone + VarDeserialize> VarDeserialize for Graph<V> {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (vertices, rest) = ORSet::deserialize_var(encoded)?;
+        let (edges, rest) = ORSet::deserialize_var(rest)?;
+        Ok((Self { vertices, edges }, rest))
+    }
+}
+//#endregion
+
+//#region GraphError
+/// ## Graph error
+///
+/// Custom error related to graph operations.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum GraphError {
+    UnknownVertex,
+}
+//#endregion
+
+#[cfg(test)]
+mod graph_tests {
+    use super::*;
+    use crate::serialization::test_var_serialization;
+
+    #[test]
+    fn neighbors_and_backlinks_work() {
+        let nid = UID::new();
+        let mut graph = Graph::new();
+        for vertex in 1u8..=3 {
+            graph.add_vertex(vertex, nid);
+        }
+        graph.add_edge(1, 2, nid).unwrap();
+        graph.add_edge(1, 3, nid).unwrap();
+        graph.add_edge(3, 2, nid).unwrap();
+        assert_eq!(graph.add_edge(1, 4, nid), Err(GraphError::UnknownVertex));
+
+        assert_eq!(graph.neighbors(&1).collect::<Vec<_>>(), vec![&2, &3]);
+        assert_eq!(graph.backlinks(&2).collect::<Vec<_>>(), vec![&1, &3]);
+
+        graph.remove_edge(&1, &2);
+        graph.remove_vertex(&3);
+        assert!(!graph.contains_edge(&1, &2));
+        assert_eq!(graph.edges().count(), 0);
+        assert_eq!(graph.vertices().collect::<Vec<_>>(), vec![&1, &2]);
+    }
+
+    #[test]
+    fn dangling_edges_are_hidden_on_all_replicas() {
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut graph_a = Graph::new();
+        graph_a.add_vertex(1u8, nid_a);
+        graph_a.add_vertex(2, nid_a);
+        let mut graph_b = graph_a.clone();
+
+        graph_a.add_edge(1, 2, nid_a).unwrap();
+        graph_b.remove_vertex(&2);
+
+        let mut merged_b = graph_b.clone();
+        merged_b.merge(&graph_a);
+        graph_a.merge(&graph_b);
+
+        assert_eq!(graph_a, merged_b, "Merge should be commutative.");
+        assert!(!graph_a.contains_edge(&1, &2));
+        assert_eq!(graph_a.neighbors(&1).count(), 0);
+
+        // The concurrent edge was never removed, so it shows up again with its target.
+        graph_a.add_vertex(2, nid_a);
+        assert!(graph_a.contains_edge(&1, &2));
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let nid = UID::new();
+        let mut graph = Graph::new();
+        graph.add_vertex(String::from("a"), nid);
+        graph.add_vertex(String::from("b"), nid);
+        graph
+            .add_edge(String::from("a"), String::from("b"), nid)
+            .unwrap();
+        graph.remove_vertex(&String::from("b"));
+        test_var_serialization(graph);
+        test_var_serialization(Graph::<u8>::new());
+    }
+}
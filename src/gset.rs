@@ -0,0 +1,147 @@
+//! # GSet CRDT
+//!
+//! Implementation of a _grow-only set_ CRDT: elements can be added but never removed, and merging
+//! two sets computes their union.
+//!
+//! ### Usage
+//!
+//! ```rust
+//! use crdts::gset::GSet;
+//!
+//! let mut set_a = GSet::new();
+//! let mut set_b = GSet::new();
+//! set_a.insert(1u8);
+//! set_b.insert(2);
+//!
+//! set_a.merge(&set_b);
+//! assert!(set_a.contains(&1) && set_a.contains(&2));
+//! ```
+use crate::serialization::{DeserializeError, VarDeserialize, VarSerialize};
+use std::collections::BTreeSet;
+
+/// ## GSet
+///
+/// Grow-only set over a generic ordered type.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GSet<T: Ord + Clone> {
+    /// ### Elements
+    ///
+    /// Elements in the set.
+    elements: BTreeSet<T>,
+}
+
+impl<T: Ord + Clone> Default for GSet<T> {
+    fn default() -> Self {
+        Self {
+            elements: BTreeSet::new(),
+        }
+    }
+}
+
+impl<T: Ord + Clone> GSet<T> {
+    /// ### New GSet
+    ///
+    /// Creates a new empty set.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ### Insert
+    ///
+    /// Adds an element to the set.
+    ///
+    /// * Returns whether the element was not yet in the set.
+    #[inline]
+    pub fn insert(&mut self, value: T) -> bool {
+        self.elements.insert(value)
+    }
+
+    /// ### Contains
+    ///
+    /// Returns whether an element is in the set.
+    #[inline]
+    pub fn contains(&self, value: &T) -> bool {
+        self.elements.contains(value)
+    }
+
+    /// ### Iterate
+    ///
+    /// Returns an iterator over the elements of the set, in ascending order.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.elements.iter()
+    }
+
+    /// ### Length
+    ///
+    /// Returns the number of elements in the set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    /// ### Is empty
+    ///
+    /// Returns whether the set has no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// ### Merge
+    ///
+    /// Merges another set into the current one (union).
+    ///
+    /// * `other` - State of another set.
+    pub fn merge(&mut self, other: &Self) {
+        self.elements.extend(other.elements.iter().cloned());
+    }
+}
+
+//#region Serialization
+impl<T: Ord + Clone + VarSerialize> VarSerialize for GSet<T> {
+    fn serialize_var(&self) -> Vec<u8> {
+        self.elements.serialize_var()
+    }
+}
+
+impl<T: Ord + Clone + VarDeserialize> VarDeserialize for GSet<T> {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (elements, rest) = BTreeSet::deserialize_var(encoded)?;
+        Ok((Self { elements }, rest))
+    }
+}
+//#endregion
+
+#[cfg(test)]
+mod gset_tests {
+    use super::*;
+    use crate::serialization::test_var_serialization;
+
+    #[test]
+    fn merge_computes_union() {
+        let mut set_a = GSet::new();
+        let mut set_b = GSet::new();
+        set_a.insert(1u8);
+        set_a.insert(2);
+        set_b.insert(2);
+        set_b.insert(3);
+
+        let mut merged_b = set_b.clone();
+        merged_b.merge(&set_a);
+        set_a.merge(&set_b);
+
+        assert_eq!(set_a.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(set_a, merged_b, "Merge should be commutative.");
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let mut set = GSet::new();
+        set.insert(String::from("a"));
+        set.insert(String::from("b"));
+        test_var_serialization(set);
+        test_var_serialization(GSet::<u8>::new());
+    }
+}
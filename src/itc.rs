@@ -0,0 +1,703 @@
+//! # Interval tree clocks
+//!
+//! Implementation of _interval tree clocks_, a causality tracking mechanism for systems where
+//! nodes come and go.
+//!
+//! Unlike a [`VClock`](crate::vclock::VClock), an interval tree clock has no entry per node.
+//! Each [`Stamp`] owns a part of the interval `[0, 1)` (its _ID_) and records the events seen so
+//! far as a tree over that interval. New nodes get their ID by forking the stamp of an existing
+//! node, and leaving nodes give their ID back by joining their stamp into another one, so the
+//! size of the stamps depends on the number of nodes currently alive rather than on every node
+//! ever seen.
+//!
+//! Stamps are compared by the events they have seen, with the same API as vector clocks
+//! ([`Stamp::happened_before`], [`Stamp::is_concurrent`] and [`Stamp::dominates`]). A stamp
+//! without ID ([`Stamp::peek`]) carries the events only and can be sent along with messages.
+//!
+//! Reference: [Interval Tree Clocks - Almeida et. al.](https://doi.org/10.1007/978-3-540-92221-6_18)
+//!
+//! ### Usage
+//!
+//! ```rust
+//! use crdts::itc::Stamp;
+//!
+//! let mut stamp_a = Stamp::new();
+//! // A new node joins the system.
+//! let mut stamp_b = stamp_a.fork();
+//!
+//! stamp_a.event().unwrap();
+//! stamp_b.event().unwrap();
+//! assert!(stamp_a.is_concurrent(&stamp_b));
+//!
+//! stamp_a.merge(&stamp_b.peek());
+//! assert!(stamp_b.happened_before(&stamp_a));
+//!
+//! // The node leaves the system and gives its ID back.
+//! let stamp = stamp_a.join(&stamp_b);
+//! assert_eq!(stamp, Stamp::new().join(&stamp));
+//! ```
+use crate::serialization::{
+    decode_varint, encode_varint, DeserializeError, VarDeserialize, VarSerialize,
+};
+use std::cmp::{max, min, Ordering};
+
+/// ## Grow cost
+///
+/// Cost of expanding a leaf of the event tree when choosing where to record an event, so that
+/// existing branches are preferred and the tree stays small.
+const GROW_COST: u64 = 1000;
+
+/// ## Max depth
+///
+/// Maximum depth of a decoded tree, so that a malicious message cannot exhaust the stack. Trees
+/// only get this deep after hundreds of nested forks.
+const MAX_DEPTH: usize = 256;
+
+//#region IdTree
+/// ## ID tree
+///
+/// Part of the interval `[0, 1)` owned by a stamp: a leaf owns either the whole sub-interval or
+/// nothing, and a node splits the sub-interval in two halves.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum IdTree {
+    Leaf(bool),
+    Node(Box<IdTree>, Box<IdTree>),
+}
+
+impl IdTree {
+    /// ### Node
+    ///
+    /// Creates a normalized node from two halves.
+    fn node(left: Self, right: Self) -> Self {
+        match (&left, &right) {
+            (Self::Leaf(left_owned), Self::Leaf(right_owned)) if left_owned == right_owned => {
+                Self::Leaf(*left_owned)
+            }
+            _ => Self::Node(Box::new(left), Box::new(right)),
+        }
+    }
+
+    /// ### Split
+    ///
+    /// Splits the owned interval in two disjoint parts.
+    fn split(&self) -> (Self, Self) {
+        match self {
+            Self::Leaf(false) => (Self::Leaf(false), Self::Leaf(false)),
+            Self::Leaf(true) => (
+                Self::node(Self::Leaf(true), Self::Leaf(false)),
+                Self::node(Self::Leaf(false), Self::Leaf(true)),
+            ),
+            Self::Node(left, right) => match (left.as_ref(), right.as_ref()) {
+                (Self::Leaf(false), right) => {
+                    let (right_a, right_b) = right.split();
+                    (
+                        Self::node(Self::Leaf(false), right_a),
+                        Self::node(Self::Leaf(false), right_b),
+                    )
+                }
+                (left, Self::Leaf(false)) => {
+                    let (left_a, left_b) = left.split();
+                    (
+                        Self::node(left_a, Self::Leaf(false)),
+                        Self::node(left_b, Self::Leaf(false)),
+                    )
+                }
+                (left, right) => (
+                    Self::node(left.clone(), Self::Leaf(false)),
+                    Self::node(Self::Leaf(false), right.clone()),
+                ),
+            },
+        }
+    }
+
+    /// ### Sum
+    ///
+    /// Returns the union of two owned intervals.
+    fn sum(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::Leaf(false), id) | (id, Self::Leaf(false)) => id.clone(),
+            (Self::Leaf(true), _) | (_, Self::Leaf(true)) => Self::Leaf(true),
+            (Self::Node(left_a, right_a), Self::Node(left_b, right_b)) => {
+                Self::node(left_a.sum(left_b), right_a.sum(right_b))
+            }
+        }
+    }
+}
+//#endregion
+
+//#region EventTree
+/// ## Event tree
+///
+/// Number of events seen over the interval `[0, 1)`: a leaf holds a number for its whole
+/// sub-interval, and a node holds a base number that is added to both of its halves.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum EventTree {
+    Leaf(u64),
+    Node(u64, Box<EventTree>, Box<EventTree>),
+}
+
+impl EventTree {
+    /// ### Node
+    ///
+    /// Creates a normalized node, moving the common part of both halves into the base.
+    fn node(base: u64, left: Self, right: Self) -> Self {
+        match (&left, &right) {
+            (Self::Leaf(left_n), Self::Leaf(right_n)) if left_n == right_n => {
+                Self::Leaf(base.saturating_add(*left_n))
+            }
+            _ => {
+                let common = min(left.min(), right.min());
+                Self::Node(
+                    base.saturating_add(common),
+                    Box::new(left.sink(common)),
+                    Box::new(right.sink(common)),
+                )
+            }
+        }
+    }
+
+    /// ### Base
+    ///
+    /// Returns the number held by the root.
+    fn base(&self) -> u64 {
+        match self {
+            Self::Leaf(n) | Self::Node(n, _, _) => *n,
+        }
+    }
+
+    /// ### Lift
+    ///
+    /// Adds a number to the root.
+    fn lift(self, amount: u64) -> Self {
+        match self {
+            Self::Leaf(n) => Self::Leaf(n.saturating_add(amount)),
+            Self::Node(n, left, right) => Self::Node(n.saturating_add(amount), left, right),
+        }
+    }
+
+    /// ### Sink
+    ///
+    /// Subtracts a number from the root.
+    fn sink(self, amount: u64) -> Self {
+        match self {
+            Self::Leaf(n) => Self::Leaf(n - amount),
+            Self::Node(n, left, right) => Self::Node(n - amount, left, right),
+        }
+    }
+
+    /// ### Min
+    ///
+    /// Returns the smallest number of events over the interval.
+    fn min(&self) -> u64 {
+        match self {
+            Self::Leaf(n) => *n,
+            Self::Node(n, left, right) => n.saturating_add(min(left.min(), right.min())),
+        }
+    }
+
+    /// ### Max
+    ///
+    /// Returns the largest number of events over the interval.
+    fn max(&self) -> u64 {
+        match self {
+            Self::Leaf(n) => *n,
+            Self::Node(n, left, right) => n.saturating_add(max(left.max(), right.max())),
+        }
+    }
+
+    /// ### Less or equal
+    ///
+    /// Returns whether the tree is smaller than or equal to another one at every point of the
+    /// interval, each tree being lifted by an offset.
+    fn leq(&self, offset: u64, other: &Self, other_offset: u64) -> bool {
+        match (self, other) {
+            (Self::Leaf(n), _) => {
+                offset.saturating_add(*n) <= other_offset.saturating_add(other.base())
+            }
+            (Self::Node(n, left, right), Self::Leaf(other_n)) => {
+                let base = offset.saturating_add(*n);
+                base <= other_offset.saturating_add(*other_n)
+                    && left.leq(base, other, other_offset)
+                    && right.leq(base, other, other_offset)
+            }
+            (Self::Node(n, left, right), Self::Node(other_n, other_left, other_right)) => {
+                let (base, other_base) = (
+                    offset.saturating_add(*n),
+                    other_offset.saturating_add(*other_n),
+                );
+                base <= other_base
+                    && left.leq(base, other_left, other_base)
+                    && right.leq(base, other_right, other_base)
+            }
+        }
+    }
+
+    /// ### Join
+    ///
+    /// Returns the pointwise maximum of two trees.
+    fn join(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::Leaf(n), Self::Leaf(other_n)) => Self::Leaf(max(*n, *other_n)),
+            (Self::Leaf(n), Self::Node(..)) => Self::expand(*n).join(other),
+            (Self::Node(..), Self::Leaf(other_n)) => self.join(&Self::expand(*other_n)),
+            (Self::Node(n, left, right), Self::Node(other_n, other_left, other_right)) => {
+                if n > other_n {
+                    return other.join(self);
+                }
+                let lift = other_n - n;
+                Self::node(
+                    *n,
+                    left.join(&other_left.as_ref().clone().lift(lift)),
+                    right.join(&other_right.as_ref().clone().lift(lift)),
+                )
+            }
+        }
+    }
+
+    /// ### Expand
+    ///
+    /// Returns a (non-normalized) node equivalent to a leaf.
+    fn expand(n: u64) -> Self {
+        Self::Node(n, Box::new(Self::Leaf(0)), Box::new(Self::Leaf(0)))
+    }
+
+    /// ### Fill
+    ///
+    /// Raises the parts of the tree owned by an ID as much as possible without creating new
+    /// events, simplifying the tree.
+    fn fill(&self, id: &IdTree) -> Self {
+        match (id, self) {
+            (IdTree::Leaf(false), _) | (_, Self::Leaf(_)) => self.clone(),
+            (IdTree::Leaf(true), _) => Self::Leaf(self.max()),
+            (IdTree::Node(id_left, id_right), Self::Node(n, left, right)) => {
+                match (id_left.as_ref(), id_right.as_ref()) {
+                    (IdTree::Leaf(true), id_right) => {
+                        let right = right.fill(id_right);
+                        let left = Self::Leaf(max(left.max(), right.min()));
+                        Self::node(*n, left, right)
+                    }
+                    (id_left, IdTree::Leaf(true)) => {
+                        let left = left.fill(id_left);
+                        let right = Self::Leaf(max(right.max(), left.min()));
+                        Self::node(*n, left, right)
+                    }
+                    (id_left, id_right) => Self::node(*n, left.fill(id_left), right.fill(id_right)),
+                }
+            }
+        }
+    }
+
+    /// ### Grow
+    ///
+    /// Records a new event in a part of the tree owned by an ID, choosing the part that keeps
+    /// the tree the smallest.
+    ///
+    /// * Returns the new tree and the cost of the change.
+    fn grow(&self, id: &IdTree) -> (Self, u64) {
+        match (id, self) {
+            (IdTree::Leaf(true), Self::Leaf(n)) => (Self::Leaf(n.saturating_add(1)), 0),
+            (IdTree::Node(..), Self::Leaf(n)) => {
+                let (tree, cost) = Self::expand(*n).grow(id);
+                (tree, cost + GROW_COST)
+            }
+            (IdTree::Node(id_left, id_right), Self::Node(n, left, right)) => {
+                match (id_left.as_ref(), id_right.as_ref()) {
+                    (IdTree::Leaf(false), id_right) => {
+                        let (right, cost) = right.grow(id_right);
+                        (Self::Node(*n, left.clone(), Box::new(right)), cost + 1)
+                    }
+                    (id_left, IdTree::Leaf(false)) => {
+                        let (left, cost) = left.grow(id_left);
+                        (Self::Node(*n, Box::new(left), right.clone()), cost + 1)
+                    }
+                    (id_left, id_right) => {
+                        let (grown_left, cost_left) = left.grow(id_left);
+                        let (grown_right, cost_right) = right.grow(id_right);
+                        if cost_left < cost_right {
+                            (
+                                Self::Node(*n, Box::new(grown_left), right.clone()),
+                                cost_left + 1,
+                            )
+                        } else {
+                            (
+                                Self::Node(*n, left.clone(), Box::new(grown_right)),
+                                cost_right + 1,
+                            )
+                        }
+                    }
+                }
+            }
+            // An empty ID cannot record events, and a full ID always fills the tree to a leaf.
+            _ => unreachable!("The tree can always be filled or grown for a non-empty ID."),
+        }
+    }
+}
+//#endregion
+
+/// ## Stamp
+///
+/// Interval tree clock of a node: the ID it owns and the events it has seen.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Stamp {
+    /// ### ID
+    ///
+    /// Part of the interval owned by the stamp.
+    id: IdTree,
+
+    /// ### Events
+    ///
+    /// Events seen by the stamp.
+    event: EventTree,
+}
+
+impl Default for Stamp {
+    fn default() -> Self {
+        Self {
+            id: IdTree::Leaf(true),
+            event: EventTree::Leaf(0),
+        }
+    }
+}
+
+impl Stamp {
+    /// ### New Stamp
+    ///
+    /// Creates the seed stamp, which owns the whole interval and has seen no events. Only the
+    /// first node of the system should create it; the others fork it.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ### Fork
+    ///
+    /// Splits the ID of the stamp in two, keeping one half and returning a stamp with the other
+    /// half for a new node. Both stamps have seen the same events.
+    pub fn fork(&mut self) -> Self {
+        let (id, other_id) = self.id.split();
+        self.id = id;
+        Self {
+            id: other_id,
+            event: self.event.clone(),
+        }
+    }
+
+    /// ### Join
+    ///
+    /// Returns a stamp owning the IDs of both stamps and having seen the events of both, e.g.
+    /// when a node leaves the system.
+    pub fn join(&self, other: &Self) -> Self {
+        Self {
+            id: self.id.sum(&other.id),
+            event: self.event.join(&other.event),
+        }
+    }
+
+    /// ### Merge
+    ///
+    /// Adds the events seen by another stamp (typically a [`Stamp::peek`] received with a
+    /// message) to the current one. Unlike [`Stamp::join`], the ID of the other stamp is ignored.
+    pub fn merge(&mut self, other: &Self) {
+        self.event = self.event.join(&other.event);
+    }
+
+    /// ### Event
+    ///
+    /// Records a new event.
+    ///
+    /// * Throws [`ItcError::AnonymousStamp`] if the stamp owns no ID.
+    pub fn event(&mut self) -> Result<(), ItcError> {
+        if self.is_anonymous() {
+            return Err(ItcError::AnonymousStamp);
+        }
+        let filled = self.event.fill(&self.id);
+        self.event = if filled != self.event {
+            filled
+        } else {
+            self.event.grow(&self.id).0
+        };
+        Ok(())
+    }
+
+    /// ### Peek
+    ///
+    /// Returns an anonymous stamp with the events of the current one, to be sent with messages.
+    pub fn peek(&self) -> Self {
+        Self {
+            id: IdTree::Leaf(false),
+            event: self.event.clone(),
+        }
+    }
+
+    /// ### Is anonymous
+    ///
+    /// Returns whether the stamp owns no ID, and therefore cannot record events.
+    #[inline]
+    pub fn is_anonymous(&self) -> bool {
+        self.id == IdTree::Leaf(false)
+    }
+
+    /// ### Compare
+    ///
+    /// Compares the events seen by two stamps, regardless of their IDs.
+    ///
+    /// * Returns [`None`] if the stamps are concurrent.
+    pub fn compare(&self, other: &Self) -> Option<Ordering> {
+        let leq = self.event.leq(0, &other.event, 0);
+        let geq = other.event.leq(0, &self.event, 0);
+        match (leq, geq) {
+            (true, true) => Some(Ordering::Equal),
+            (true, false) => Some(Ordering::Less),
+            (false, true) => Some(Ordering::Greater),
+            (false, false) => None,
+        }
+    }
+
+    /// ### Happened before
+    ///
+    /// Returns whether the events seen by the stamp causally precede the ones seen by another
+    /// stamp.
+    #[inline]
+    pub fn happened_before(&self, other: &Self) -> bool {
+        self.compare(other) == Some(Ordering::Less)
+    }
+
+    /// ### Is concurrent
+    ///
+    /// Returns whether each stamp has seen events the other has not.
+    #[inline]
+    pub fn is_concurrent(&self, other: &Self) -> bool {
+        self.compare(other).is_none()
+    }
+
+    /// ### Dominates
+    ///
+    /// Returns whether the stamp has seen all the events of another stamp.
+    #[inline]
+    pub fn dominates(&self, other: &Self) -> bool {
+        other.event.leq(0, &self.event, 0)
+    }
+}
+
+//#region Serialization
+/// IDs are encoded in pre-order with one byte per tree node: 0 and 1 for leaves that own nothing
+/// or everything, 2 for inner nodes. Events are encoded in pre-order with a byte per tree node (0
+/// for leaves, 1 for inner nodes) followed by its number as a varint.
+///
+/// Decoded trees are normalized, so that any stamp that decodes successfully can be used, and
+/// trees deeper than [`MAX_DEPTH`] are rejected.
+impl VarSerialize for Stamp {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        encode_id(&self.id, &mut encoded);
+        encode_event(&self.event, &mut encoded);
+        encoded
+    }
+}
+
+impl VarDeserialize for Stamp {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (id, rest) = decode_id(encoded, 0)?;
+        let (event, rest) = decode_event(rest, 0)?;
+        Ok((Self { id, event }, rest))
+    }
+}
+
+fn encode_id(id: &IdTree, encoded: &mut Vec<u8>) {
+    match id {
+        IdTree::Leaf(owned) => encoded.push(*owned as u8),
+        IdTree::Node(left, right) => {
+            encoded.push(2);
+            encode_id(left, encoded);
+            encode_id(right, encoded);
+        }
+    }
+}
+
+fn decode_id(encoded: &[u8], depth: usize) -> Result<(IdTree, &[u8]), DeserializeError> {
+    if depth > MAX_DEPTH {
+        return Err(DeserializeError::InvalidValue);
+    }
+    let (tag, rest) = encoded
+        .split_first()
+        .ok_or(DeserializeError::UnexpectedEnd)?;
+    match tag {
+        0 | 1 => Ok((IdTree::Leaf(*tag == 1), rest)),
+        2 => {
+            let (left, rest) = decode_id(rest, depth + 1)?;
+            let (right, rest) = decode_id(rest, depth + 1)?;
+            Ok((IdTree::node(left, right), rest))
+        }
+        _ => Err(DeserializeError::InvalidValue),
+    }
+}
+
+fn encode_event(event: &EventTree, encoded: &mut Vec<u8>) {
+    match event {
+        EventTree::Leaf(n) => {
+            encoded.push(0);
+            encoded.extend(encode_varint(*n));
+        }
+        EventTree::Node(n, left, right) => {
+            encoded.push(1);
+            encoded.extend(encode_varint(*n));
+            encode_event(left, encoded);
+            encode_event(right, encoded);
+        }
+    }
+}
+
+fn decode_event(encoded: &[u8], depth: usize) -> Result<(EventTree, &[u8]), DeserializeError> {
+    if depth > MAX_DEPTH {
+        return Err(DeserializeError::InvalidValue);
+    }
+    let (tag, rest) = encoded
+        .split_first()
+        .ok_or(DeserializeError::UnexpectedEnd)?;
+    let (n, rest) = decode_varint(rest)?;
+    match tag {
+        0 => Ok((EventTree::Leaf(n), rest)),
+        1 => {
+            let (left, rest) = decode_event(rest, depth + 1)?;
+            let (right, rest) = decode_event(rest, depth + 1)?;
+            Ok((EventTree::node(n, left, right), rest))
+        }
+        _ => Err(DeserializeError::InvalidValue),
+    }
+}
+//#endregion
+
+//#region ItcError
+/// ## ITC error
+///
+/// Custom error related to interval tree clock operations.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ItcError {
+    AnonymousStamp,
+}
+//#endregion
+
+#[cfg(test)]
+mod itc_tests {
+    use super::*;
+    use crate::serialization::test_var_serialization;
+
+    #[test]
+    fn causal_relations_work() {
+        let mut stamp_a = Stamp::new();
+        let mut stamp_b = stamp_a.fork();
+        let mut stamp_c = stamp_b.fork();
+        assert_eq!(stamp_a.compare(&stamp_c), Some(Ordering::Equal));
+
+        stamp_a.event().unwrap();
+        assert!(stamp_c.happened_before(&stamp_a));
+        stamp_b.event().unwrap();
+        assert!(stamp_a.is_concurrent(&stamp_b));
+
+        stamp_c.merge(&stamp_a.peek());
+        stamp_c.merge(&stamp_b.peek());
+        assert!(stamp_c.dominates(&stamp_a) && stamp_c.dominates(&stamp_b));
+        stamp_c.event().unwrap();
+        assert!(stamp_a.happened_before(&stamp_c) && stamp_b.happened_before(&stamp_c));
+        assert!(!stamp_a.dominates(&stamp_c));
+
+        assert_eq!(stamp_a.peek().event(), Err(ItcError::AnonymousStamp));
+    }
+
+    #[test]
+    fn merge_keeps_the_id() {
+        let mut stamp_a = Stamp::new();
+        let mut stamp_b = stamp_a.fork();
+        let id = stamp_a.id.clone();
+        stamp_b.event().unwrap();
+
+        stamp_a.merge(&stamp_b);
+        assert_eq!(stamp_a.id, id, "Merging should not take the other ID.");
+        assert!(stamp_a.dominates(&stamp_b));
+
+        stamp_a.event().unwrap();
+        stamp_b.event().unwrap();
+        assert!(
+            stamp_a.is_concurrent(&stamp_b),
+            "Events on distinct IDs should stay concurrent."
+        );
+    }
+
+    #[test]
+    fn retired_ids_are_reclaimed() {
+        let mut stamp_a = Stamp::new();
+        let mut stamp_b = stamp_a.fork();
+        let mut stamp_c = stamp_b.fork();
+        for stamp in [&mut stamp_a, &mut stamp_b, &mut stamp_c] {
+            stamp.event().unwrap();
+        }
+
+        let stamp = stamp_a.join(&stamp_b).join(&stamp_c);
+        assert_eq!(
+            stamp.id,
+            IdTree::Leaf(true),
+            "The whole interval is owned again."
+        );
+        assert_eq!(
+            stamp.event,
+            EventTree::Leaf(1),
+            "Events collapse into a leaf."
+        );
+        let mut filled = stamp.clone();
+        filled.event().unwrap();
+        assert_eq!(filled.event, EventTree::Leaf(2));
+        assert!(stamp.happened_before(&filled));
+    }
+
+    #[test]
+    fn decoded_stamps_are_safe_to_use() {
+        // Non-normalized ID tree with an empty interval on both sides.
+        let (mut stamp, _) = Stamp::deserialize_var(&[2, 0, 0, 0, 0]).unwrap();
+        assert!(stamp.is_anonymous());
+        assert_eq!(stamp.event(), Err(ItcError::AnonymousStamp));
+
+        let mut max = vec![1, 0];
+        max.extend(encode_varint(u64::MAX));
+        let (mut stamp_max, _) = Stamp::deserialize_var(&max).unwrap();
+        let mut uneven = vec![1, 1];
+        uneven.extend(encode_varint(u64::MAX));
+        uneven.extend([0, 0, 0, 1]);
+        let (stamp_uneven, _) = Stamp::deserialize_var(&uneven).unwrap();
+
+        assert!(stamp_uneven.dominates(&stamp_max) && stamp_max.dominates(&stamp_uneven));
+        stamp_max.event().unwrap();
+        test_var_serialization(stamp_max.clone());
+        assert!(stamp_max.dominates(&stamp_uneven));
+        assert!(Stamp::new().happened_before(&stamp_uneven));
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let mut stamp_a = Stamp::new();
+        let mut stamp_b = stamp_a.fork();
+        stamp_a.event().unwrap();
+        for _ in 0..100 {
+            stamp_b.event().unwrap();
+        }
+        assert_eq!(Stamp::new().serialize_var(), vec![1, 0, 0]);
+        test_var_serialization(stamp_a.peek());
+        test_var_serialization(stamp_b.clone());
+        test_var_serialization(stamp_a.join(&stamp_b));
+        assert_eq!(
+            Stamp::deserialize_var(&[3, 0]),
+            Err(DeserializeError::InvalidValue)
+        );
+
+        let deep_id = vec![2; 1_000_000];
+        assert_eq!(
+            Stamp::deserialize_var(&deep_id),
+            Err(DeserializeError::InvalidValue)
+        );
+        let mut deep_event = vec![1];
+        deep_event.extend([1, 0].repeat(1_000_000));
+        assert_eq!(
+            Stamp::deserialize_var(&deep_event),
+            Err(DeserializeError::InvalidValue)
+        );
+    }
+}
@@ -1,15 +1,37 @@
 //! # K-CRDT
 //!
 //! A library to manage a collection of CRDTS in a WASM context.
+pub mod bcounter;
 #[cfg(feature = "client")]
 pub mod client;
+pub mod countermap;
+pub mod document;
+pub mod dot;
+pub mod dotcontext;
+pub mod flag;
 pub mod gcounter;
+pub mod glog;
+pub mod graph;
+pub mod gset;
+pub mod itc;
+pub mod lwwmap;
 pub mod lwwregister;
+pub mod maxregister;
+pub mod mvregister;
+pub mod orset;
 pub mod pncounter;
+pub mod position;
+pub mod rcounter;
+pub mod record;
+pub mod rga;
+pub mod richtext;
 pub mod serialization;
 #[cfg(feature = "server")]
 pub mod server;
 pub mod steps;
+pub mod text;
 pub mod time;
+pub mod tree;
+pub mod twopset;
 pub mod uid;
 pub mod vclock;
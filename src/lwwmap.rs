@@ -0,0 +1,382 @@
+//! # LWWMap CRDT
+//!
+//! The *last-write-wins map (LWWMap)* CRDT maps keys to [`LWWRegister`]s. Every entry keeps the
+//! ID of the node that performed the last write, so that concurrent writes with the same timestamp
+//! are decided deterministically by the node IDs.
+//!
+//! Deletes are stored as timestamped tombstones. A write with an older timestamp than the
+//! deletion is discarded on merge, so a stale write cannot bring a deleted key back.
+//!
+//! ### Usage
+//!
+//! ```rust
+//! use crdts::lwwmap::LWWMap;
+//! use crdts::time::hlc::{HybridLogicalClock, SysTimeHLC};
+//! use crdts::uid::UID;
+//!
+//! let mut hlc = SysTimeHLC::default();
+//! let (nid_a, nid_b) = (UID::new(), UID::new());
+//!
+//! let mut map_a = LWWMap::<u8, bool>::new();
+//! let mut map_b = LWWMap::new();
+//! map_b.put(1, true, nid_b, hlc.generate_timestamp());
+//! map_a.delete(1, nid_a, hlc.generate_timestamp());
+//!
+//! map_a.merge(&map_b);
+//! assert_eq!(map_a.get(&1), None, "The later deletion wins over the stale write.");
+//! ```
+use crate::{
+    lwwregister::LWWRegister,
+    serialization::{DeserializeError, VarDeserialize, VarSerialize},
+    time::timestamp::Timestamp,
+    uid::UID,
+};
+use std::collections::BTreeMap;
+
+//#region Entry
+/// ## LWWMap entry
+///
+/// Value of a single key of the map: a register together with the ID of the node that
+/// performed the last write and whether that write was a deletion.
+#[derive(Clone, Default, Debug, Eq, PartialEq)]
+pub struct LWWMapEntry<V: Clone + Default> {
+    /// ### Register
+    ///
+    /// Register holding the last written value and its timestamp.
+    register: LWWRegister<V>,
+
+    /// ### Node ID
+    ///
+    /// ID of the node that performed the last write.
+    nid: UID,
+
+    /// ### Deleted
+    ///
+    /// Whether the last write was a deletion (tombstone).
+    deleted: bool,
+}
+
+impl<V: Clone + Default> LWWMapEntry<V> {
+    /// ### New entry
+    ///
+    /// Creates an entry holding a value written by node `nid` at time `ts`.
+    #[inline]
+    pub fn new(value: V, nid: UID, ts: Timestamp) -> Self {
+        Self {
+            register: LWWRegister::new(ts, value),
+            nid,
+            deleted: false,
+        }
+    }
+
+    /// ### New tombstone
+    ///
+    /// Creates an entry marking a deletion performed by node `nid` at time `ts`.
+    #[inline]
+    pub fn tombstone(nid: UID, ts: Timestamp) -> Self {
+        Self {
+            register: LWWRegister::new(ts, V::default()),
+            nid,
+            deleted: true,
+        }
+    }
+
+    /// ### Get timestamp
+    ///
+    /// Returns the timestamp of the last write.
+    #[inline]
+    pub fn get_timestamp(&self) -> Timestamp {
+        self.register.get_timestamp()
+    }
+
+    /// ### Get node ID
+    ///
+    /// Returns the ID of the node that performed the last write.
+    #[inline]
+    pub fn get_nid(&self) -> UID {
+        self.nid
+    }
+
+    /// ### Get value
+    ///
+    /// Returns a copy of the value of the entry, or `None` if the entry is a tombstone.
+    #[inline]
+    pub fn get_value(&self) -> Option<V> {
+        if self.deleted {
+            None
+        } else {
+            Some(self.register.get_value())
+        }
+    }
+
+    /// ### Is deleted
+    ///
+    /// Returns whether the entry is a tombstone.
+    #[inline]
+    pub fn is_deleted(&self) -> bool {
+        self.deleted
+    }
+
+    /// ### Merge
+    ///
+    /// Merges another entry for the same key. The write with the largest timestamp wins.
+    /// Tied cases are decided by the order of the node IDs.
+    ///
+    /// * `other` - Entry to be merged.
+    pub fn merge(&mut self, other: &Self) {
+        if (self.get_timestamp(), self.nid) < (other.get_timestamp(), other.nid) {
+            self.register.merge(&other.register, self.nid, other.nid);
+            self.nid = other.nid;
+            self.deleted = other.deleted;
+        }
+    }
+}
+
+//#region Serialization
+impl<V: Clone + Default + VarSerialize> VarSerialize for LWWMapEntry<V> {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = self.register.serialize_var();
+        encoded.extend(self.nid.serialize_var());
+        encoded.extend(self.deleted.serialize_var());
+        encoded
+    }
+}
+
+impl<V: Clone + Default + VarDeserialize> VarDeserialize for LWWMapEntry<V> {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (register, rest) = LWWRegister::deserialize_var(encoded)?;
+        let (nid, rest) = UID::deserialize_var(rest)?;
+        let (deleted, rest) = bool::deserialize_var(rest)?;
+        Ok((
+            Self {
+                register,
+                nid,
+                deleted,
+            },
+            rest,
+        ))
+    }
+}
+//#endregion
+//#endregion
+
+/// ## LWWMap
+///
+/// Map whose entries are last-write-wins registers.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LWWMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone + Default,
+{
+    /// ### Entries
+    ///
+    /// Entries of the map, including tombstones.
+    entries: BTreeMap<K, LWWMapEntry<V>>,
+}
+
+impl<K, V> Default for LWWMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone + Default,
+{
+    fn default() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+        }
+    }
+}
+
+impl<K, V> LWWMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone + Default,
+{
+    /// ### New LWWMap
+    ///
+    /// Creates a new empty map.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ### Put
+    ///
+    /// Writes a value for a key. The write only takes effect if it is newer than the current
+    /// entry for the key.
+    ///
+    /// * `key` - Key to write.
+    /// * `value` - Value to write.
+    /// * `nid` - ID of the current node.
+    /// * `ts` - Timestamp of the operation.
+    pub fn put(&mut self, key: K, value: V, nid: UID, ts: Timestamp) {
+        self.merge_entry(key, &LWWMapEntry::new(value, nid, ts));
+    }
+
+    /// ### Delete
+    ///
+    /// Deletes a key by writing a tombstone for it.
+    ///
+    /// * `key` - Key to delete.
+    /// * `nid` - ID of the current node.
+    /// * `ts` - Timestamp of the operation.
+    pub fn delete(&mut self, key: K, nid: UID, ts: Timestamp) {
+        self.merge_entry(key, &LWWMapEntry::tombstone(nid, ts));
+    }
+
+    /// ### Get
+    ///
+    /// Returns a copy of the value for a key, or `None` if the key is absent or deleted.
+    #[inline]
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.entries.get(key).and_then(|entry| entry.get_value())
+    }
+
+    /// ### Get entry
+    ///
+    /// Returns the entry for a key, including tombstones.
+    #[inline]
+    pub fn get_entry(&self, key: &K) -> Option<&LWWMapEntry<V>> {
+        self.entries.get(key)
+    }
+
+    /// ### Contains key
+    ///
+    /// Returns whether the map holds a value (not a tombstone) for a key.
+    #[inline]
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get_entry(key).is_some_and(|entry| !entry.is_deleted())
+    }
+
+    /// ### Iterate
+    ///
+    /// Returns an iterator over the keys and values of the map, skipping tombstones.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, V)> {
+        self.entries
+            .iter()
+            .filter_map(|(key, entry)| entry.get_value().map(|value| (key, value)))
+    }
+
+    /// ### Merge entry
+    ///
+    /// Merges a single entry (usually received from another node) into the map.
+    ///
+    /// * `key` - Key of the entry.
+    /// * `entry` - Entry to be merged.
+    pub fn merge_entry(&mut self, key: K, entry: &LWWMapEntry<V>) {
+        match self.entries.get_mut(&key) {
+            Some(current) => current.merge(entry),
+            None => {
+                self.entries.insert(key, entry.clone());
+            }
+        }
+    }
+
+    /// ### Merge
+    ///
+    /// Merges the state of another map into the current one, entry by entry.
+    ///
+    /// * `other` - State of another map.
+    pub fn merge(&mut self, other: &Self) {
+        for (key, entry) in &other.entries {
+            self.merge_entry(key.clone(), entry);
+        }
+    }
+}
+
+//#region Serialization
+impl<K, V> VarSerialize for LWWMap<K, V>
+where
+    K: Ord + Clone + VarSerialize,
+    V: Clone + Default + VarSerialize,
+{
+    fn serialize_var(&self) -> Vec<u8> {
+        self.entries.serialize_var()
+    }
+}
+
+impl<K, V> VarDeserialize for LWWMap<K, V>
+where
+    K: Ord + Clone + VarDeserialize,
+    V: Clone + Default + VarDeserialize,
+{
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (entries, rest) = BTreeMap::deserialize_var(encoded)?;
+        Ok((Self { entries }, rest))
+    }
+}
+//#endregion
+
+#[cfg(test)]
+mod lwwmap_tests {
+    use super::*;
+    use crate::serialization::test_var_serialization;
+    use crate::time::hlc::{HybridLogicalClock, SysTimeHLC};
+
+    #[test]
+    fn put_and_delete_work() {
+        let mut hlc = SysTimeHLC::default();
+        let nid = UID::new();
+        let mut map = LWWMap::<u8, bool>::new();
+
+        map.put(1, true, nid, hlc.generate_timestamp());
+        assert_eq!(map.get(&1), Some(true));
+
+        map.delete(1, nid, hlc.generate_timestamp());
+        assert_eq!(map.get(&1), None, "Deleted keys should have no value.");
+        assert!(map.get_entry(&1).unwrap().is_deleted());
+
+        map.put(1, false, nid, hlc.generate_timestamp());
+        assert_eq!(
+            map.get(&1),
+            Some(false),
+            "Newer writes should revive deleted keys."
+        );
+    }
+
+    #[test]
+    fn stale_put_does_not_revive_key() {
+        let mut hlc = SysTimeHLC::default();
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+
+        let stale_ts = hlc.generate_timestamp();
+        let mut map_a = LWWMap::<u8, bool>::new();
+        map_a.delete(1, nid_a, hlc.generate_timestamp());
+
+        let mut map_b = LWWMap::new();
+        map_b.put(1, true, nid_b, stale_ts);
+
+        let mut merged_b = map_b.clone();
+        merged_b.merge(&map_a);
+        map_a.merge(&map_b);
+
+        assert_eq!(map_a.get(&1), None, "Stale writes should be discarded.");
+        assert_eq!(map_a, merged_b, "Merge should be commutative.");
+    }
+
+    #[test]
+    fn ties_are_decided_by_node_id() {
+        let ts = SysTimeHLC::default().generate_timestamp();
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let winner = nid_a > nid_b;
+
+        let mut map_a = LWWMap::<u8, bool>::new();
+        map_a.put(1, true, nid_a, ts);
+        let mut map_b = LWWMap::new();
+        map_b.put(1, false, nid_b, ts);
+
+        map_a.merge(&map_b);
+        assert_eq!(map_a.get(&1), Some(winner));
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let mut hlc = SysTimeHLC::default();
+        let nid = UID::new();
+        let mut map = LWWMap::<u8, bool>::new();
+        map.put(1, true, nid, hlc.generate_timestamp());
+        map.put(2, false, nid, hlc.generate_timestamp());
+        map.delete(2, nid, hlc.generate_timestamp());
+        test_var_serialization(map);
+    }
+}
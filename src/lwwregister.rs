@@ -3,8 +3,18 @@
 //! The *last-write-wins register (LWWRegister)* CRDT implements a wrapper over a generic type
 //! that stores a timestamp as metadata and allows for conflict-free resolution using a simple
 //! last-write-wins strategy.
+//!
+//! ### Serialization
+//!
+//! Registers over values with a fixed-size encoding (such as `bool`) implement the fixed-size
+//! [`Serialize`] trait: the encoded timestamp followed by the
+//! encoded value. Any register over a value implementing [`VarSerialize`] can be encoded with
+//! the variable-size traits in the same layout. This covers registers over `String` and
+//! `Vec<u8>` (encoded with a length prefix) as well as `i64` and `f64`.
 use crate::{
-    serialization::{Deserialize, Serialize},
+    serialization::{
+        Deserialize, DeserializeError, Serialize, VarDeserialize, VarSerialize, BOOL_SIZE, TS_SIZE,
+    },
     time::timestamp::Timestamp,
     uid::UID,
 };
@@ -14,10 +24,7 @@ use wasm_bindgen::UnwrapThrowExt;
 ///
 /// Data structure representing a last-write-wins register wrapping a generic type.
 #[derive(Clone, Default, Debug, Eq, PartialEq)]
-pub struct LWWRegister<T, const T_BYTES: usize>
-where
-    T: Clone + Default + Eq + PartialEq + Serialize<T_BYTES> + Deserialize<T_BYTES>,
-{
+pub struct LWWRegister<T: Clone> {
     /// ### Timestamp
     ///
     /// HLC timestamp indicating the last update to the register.
@@ -31,10 +38,7 @@ where
     value: T,
 }
 
-impl<T, const T_BYTES: usize> LWWRegister<T, T_BYTES>
-where
-    T: Clone + Default + Eq + PartialEq + Serialize<T_BYTES> + Deserialize<T_BYTES>,
-{
+impl<T: Clone> LWWRegister<T> {
     /// ### New LWWRegister
     ///
     /// Creates a new LWWRegister that wraps an arbitrary value.
@@ -92,37 +96,48 @@ where
 }
 
 //#region Serialization
-// Serialize
-impl<T, const NUM_BYTES: usize, const T_BYTES: usize> Serialize<NUM_BYTES>
-    for LWWRegister<T, T_BYTES>
-where
-    T: Clone + Default + Eq + PartialEq + Serialize<T_BYTES> + Deserialize<T_BYTES>,
-{
-    fn serialize(&self) -> [u8; NUM_BYTES] {
-        let encoded_ts = self.ts.serialize();
-        let encoded_val = self.value.serialize();
-        encoded_ts
-            .iter()
-            .chain(&encoded_val)
-            .map(|&s| s)
-            .collect::<Vec<u8>>()
-            .try_into()
-            .unwrap_throw()
+/// ## Implement register serialization
+///
+/// Implements the fixed-size serialization traits for registers over a type with a fixed-size
+/// encoding. The register is encoded as the timestamp followed by the value.
+macro_rules! impl_register_serialization {
+    ($t:ty, $size:expr) => {
+        impl Serialize<{ TS_SIZE + $size }> for LWWRegister<$t> {
+            fn serialize(&self) -> [u8; TS_SIZE + $size] {
+                let mut encoded = [0u8; TS_SIZE + $size];
+                encoded[..TS_SIZE].copy_from_slice(&self.ts.serialize());
+                encoded[TS_SIZE..].copy_from_slice(&self.value.serialize());
+                encoded
+            }
+        }
+
+        impl Deserialize<{ TS_SIZE + $size }> for LWWRegister<$t> {
+            fn deserialize(encoded: [u8; TS_SIZE + $size]) -> Self {
+                let (ts, val) = encoded.split_at(TS_SIZE);
+                Self {
+                    ts: Timestamp::deserialize(ts.try_into().unwrap_throw()),
+                    value: <$t>::deserialize(val.try_into().unwrap_throw()),
+                }
+            }
+        }
+    };
+}
+
+impl_register_serialization!(bool, BOOL_SIZE);
+
+impl<T: Clone + VarSerialize> VarSerialize for LWWRegister<T> {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = self.ts.serialize_var();
+        encoded.extend(self.value.serialize_var());
+        encoded
     }
 }
 
-// Deserialize
-impl<T, const NUM_BYTES: usize, const T_BYTES: usize> Deserialize<NUM_BYTES>
-    for LWWRegister<T, T_BYTES>
-where
-    T: Clone + Default + Eq + PartialEq + Serialize<T_BYTES> + Deserialize<T_BYTES>,
-{
-    fn deserialize(encoded: [u8; NUM_BYTES]) -> Self {
-        let (ts, val) = encoded.split_at(8);
-        Self {
-            ts: Timestamp::deserialize(ts.try_into().unwrap_throw()),
-            value: T::deserialize(val.try_into().unwrap_throw()),
-        }
+impl<T: Clone + VarDeserialize> VarDeserialize for LWWRegister<T> {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (ts, rest) = Timestamp::deserialize_var(encoded)?;
+        let (value, rest) = T::deserialize_var(rest)?;
+        Ok((Self { ts, value }, rest))
     }
 }
 //#endregion
@@ -130,10 +145,29 @@ where
 #[cfg(test)]
 mod lwwregister_tests {
     use super::*;
-    use crate::serialization::{test_serialization, BOOL_SIZE};
+    use crate::serialization::{test_serialization, test_var_serialization, LEN_SIZE};
 
     #[test]
     fn bool_register_serialization_deserialization_works() {
-        test_serialization::<LWWRegister<bool, BOOL_SIZE>, 9>();
+        test_serialization::<LWWRegister<bool>, 9>();
+        test_serialization::<LWWRegister<bool>, { TS_SIZE + BOOL_SIZE }>();
+        test_var_serialization(LWWRegister::new(Timestamp::default(), true));
+    }
+
+    #[test]
+    fn var_register_serialization_deserialization_works() {
+        let ts = Timestamp::default();
+        test_var_serialization(LWWRegister::new(ts, String::from("hello")));
+        test_var_serialization(LWWRegister::new(ts, String::new()));
+        test_var_serialization(LWWRegister::new(ts, vec![1u8, 2, 3]));
+        test_var_serialization(LWWRegister::new(ts, -42i64));
+        test_var_serialization(LWWRegister::new(ts, 0.5f64));
+
+        let encoded = LWWRegister::new(ts, String::from("ab")).serialize_var();
+        assert_eq!(
+            encoded.len(),
+            TS_SIZE + LEN_SIZE + 2,
+            "Strings are length-prefixed."
+        );
     }
 }
@@ -0,0 +1,242 @@
+//! # MaxRegister and MinRegister CRDTs
+//!
+//! Registers over ordered values that only move in one direction: a _MaxRegister_ keeps the
+//! largest value ever written, a _MinRegister_ keeps the smallest one. Merging keeps the maximum
+//! (respectively minimum) of both values, so no timestamps are needed.
+//!
+//! A typical use is a "last seen" time, stored as a `MaxRegister<Timestamp>`.
+//!
+//! ### Serialization
+//!
+//! The encoding of a register is the encoding of its value, so a register over a type with a
+//! fixed-size encoding has the same fixed size.
+//!
+//! ### Usage
+//!
+//! ```rust
+//! use crdts::maxregister::MaxRegister;
+//! use crdts::time::hlc::{HybridLogicalClock, SysTimeHLC};
+//!
+//! let mut hlc = SysTimeHLC::default();
+//! let earlier = hlc.generate_timestamp();
+//! let later = hlc.generate_timestamp();
+//!
+//! let mut last_seen_a = MaxRegister::new(later);
+//! let last_seen_b = MaxRegister::new(earlier);
+//! last_seen_a.merge(&last_seen_b);
+//! assert_eq!(last_seen_a.get_value(), later);
+//! ```
+use crate::serialization::{
+    Deserialize, DeserializeError, Serialize, VarDeserialize, VarSerialize,
+};
+
+//#region MaxRegister
+/// ## MaxRegister
+///
+/// Register keeping the largest value written to it.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MaxRegister<T: Ord + Clone> {
+    /// ### Value
+    ///
+    /// Largest value written so far.
+    value: T,
+}
+
+impl<T: Ord + Clone> MaxRegister<T> {
+    /// ### New MaxRegister
+    ///
+    /// Creates a new register holding an initial value.
+    #[inline]
+    pub fn new(value: T) -> Self {
+        Self { value }
+    }
+
+    /// ### Get value
+    ///
+    /// Returns a copy of the largest value written so far.
+    #[inline]
+    pub fn get_value(&self) -> T {
+        self.value.clone()
+    }
+
+    /// ### Set
+    ///
+    /// Writes a value. The write only takes effect if the value is larger than the current one.
+    #[inline]
+    pub fn set(&mut self, value: T) {
+        if value > self.value {
+            self.value = value;
+        }
+    }
+
+    /// ### Merge
+    ///
+    /// Merges another register into the current one, keeping the largest value.
+    ///
+    /// * `other` - State of another register.
+    #[inline]
+    pub fn merge(&mut self, other: &Self) {
+        self.set(other.value.clone());
+    }
+}
+
+impl<T: Ord + Clone + Serialize<N>, const N: usize> Serialize<N> for MaxRegister<T> {
+    fn serialize(&self) -> [u8; N] {
+        self.value.serialize()
+    }
+}
+
+impl<T: Ord + Clone + Deserialize<N>, const N: usize> Deserialize<N> for MaxRegister<T> {
+    fn deserialize(encoded: [u8; N]) -> Self {
+        Self {
+            value: T::deserialize(encoded),
+        }
+    }
+}
+
+impl<T: Ord + Clone + VarSerialize> VarSerialize for MaxRegister<T> {
+    fn serialize_var(&self) -> Vec<u8> {
+        self.value.serialize_var()
+    }
+}
+
+impl<T: Ord + Clone + VarDeserialize> VarDeserialize for MaxRegister<T> {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (value, rest) = T::deserialize_var(encoded)?;
+        Ok((Self { value }, rest))
+    }
+}
+//#endregion
+
+//#region MinRegister
+/// ## MinRegister
+///
+/// Register keeping the smallest value written to it.
+///
+/// * Does not implement [`Default`]: the default of most types is their minimum, which would
+///   ignore every write.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MinRegister<T: Ord + Clone> {
+    /// ### Value
+    ///
+    /// Smallest value written so far.
+    value: T,
+}
+
+impl<T: Ord + Clone> MinRegister<T> {
+    /// ### New MinRegister
+    ///
+    /// Creates a new register holding an initial value.
+    #[inline]
+    pub fn new(value: T) -> Self {
+        Self { value }
+    }
+
+    /// ### Get value
+    ///
+    /// Returns a copy of the smallest value written so far.
+    #[inline]
+    pub fn get_value(&self) -> T {
+        self.value.clone()
+    }
+
+    /// ### Set
+    ///
+    /// Writes a value. The write only takes effect if the value is smaller than the current one.
+    #[inline]
+    pub fn set(&mut self, value: T) {
+        if value < self.value {
+            self.value = value;
+        }
+    }
+
+    /// ### Merge
+    ///
+    /// Merges another register into the current one, keeping the smallest value.
+    ///
+    /// * `other` - State of another register.
+    #[inline]
+    pub fn merge(&mut self, other: &Self) {
+        self.set(other.value.clone());
+    }
+}
+
+impl<T: Ord + Clone + Serialize<N>, const N: usize> Serialize<N> for MinRegister<T> {
+    fn serialize(&self) -> [u8; N] {
+        self.value.serialize()
+    }
+}
+
+impl<T: Ord + Clone + Deserialize<N>, const N: usize> Deserialize<N> for MinRegister<T> {
+    fn deserialize(encoded: [u8; N]) -> Self {
+        Self {
+            value: T::deserialize(encoded),
+        }
+    }
+}
+
+impl<T: Ord + Clone + VarSerialize> VarSerialize for MinRegister<T> {
+    fn serialize_var(&self) -> Vec<u8> {
+        self.value.serialize_var()
+    }
+}
+
+impl<T: Ord + Clone + VarDeserialize> VarDeserialize for MinRegister<T> {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (value, rest) = T::deserialize_var(encoded)?;
+        Ok((Self { value }, rest))
+    }
+}
+//#endregion
+
+#[cfg(test)]
+mod maxregister_tests {
+    use super::*;
+    use crate::serialization::{
+        test_serialization, test_var_serialization, BOOL_SIZE, TS_SIZE, UID_SIZE,
+    };
+    use crate::time::hlc::{HybridLogicalClock, SysTimeHLC};
+    use crate::time::timestamp::Timestamp;
+    use crate::uid::UID;
+
+    #[test]
+    fn registers_keep_extreme_values() {
+        let mut max = MaxRegister::new(5u32);
+        let mut min = MinRegister::new(5u32);
+        for value in [3, 8, 6] {
+            max.set(value);
+            min.set(value);
+        }
+        assert_eq!(max.get_value(), 8);
+        assert_eq!(min.get_value(), 3);
+
+        let mut other_max = MaxRegister::new(9);
+        other_max.merge(&max);
+        max.merge(&MaxRegister::new(9));
+        assert_eq!(max, other_max, "Merge should be commutative.");
+
+        let mut other_min = MinRegister::new(1);
+        other_min.merge(&min);
+        min.merge(&MinRegister::new(1));
+        assert_eq!(min, other_min, "Merge should be commutative.");
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        test_serialization::<MaxRegister<Timestamp>, TS_SIZE>();
+        test_serialization::<MaxRegister<UID>, UID_SIZE>();
+        test_serialization::<MaxRegister<bool>, BOOL_SIZE>();
+
+        let ts = SysTimeHLC::default().generate_timestamp();
+        let min = MinRegister::new(ts);
+        assert_eq!(MinRegister::deserialize(min.serialize()), min);
+        assert_eq!(
+            MaxRegister::new(ts).serialize(),
+            ts.serialize(),
+            "A register is encoded as its value."
+        );
+        test_var_serialization(MaxRegister::new(ts));
+        test_var_serialization(MinRegister::new(String::from("a")));
+        test_var_serialization(MinRegister::new(true));
+    }
+}
@@ -0,0 +1,225 @@
+//! # MVRegister CRDT
+//!
+//! The *multi-value register (MVRegister)* CRDT keeps every value written concurrently instead of
+//! picking a winner. Each value is tagged with a [`VClock`] capturing the writes its author had
+//! observed. A write replaces all the values it has observed, so concurrent values are kept until
+//! a later write supersedes all of them.
+//!
+//! This makes conflicts visible to the user, who can then resolve them by writing a new value.
+//!
+//! ### Usage
+//!
+//! ```rust
+//! use crdts::mvregister::MVRegister;
+//! use crdts::uid::UID;
+//!
+//! let (nid_a, nid_b) = (UID::new(), UID::new());
+//!
+//! let mut reg_a = MVRegister::new();
+//! let mut reg_b = MVRegister::new();
+//! reg_a.set(true, nid_a);
+//! reg_b.set(false, nid_b);
+//!
+//! reg_a.merge(&reg_b);
+//! assert_eq!(reg_a.values().len(), 2, "Concurrent writes should be kept.");
+//!
+//! reg_a.set(true, nid_a);
+//! assert_eq!(reg_a.values(), vec![true], "A later write supersedes all observed values.");
+//! ```
+use crate::{
+    serialization::{DeserializeError, VarDeserialize, VarSerialize},
+    uid::UID,
+    vclock::VClock,
+};
+use std::cmp::Ordering::Equal;
+
+/// ## MVRegister
+///
+/// Data structure representing a multi-value register wrapping a generic type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MVRegister<T: Clone + Eq> {
+    /// ### Entries
+    ///
+    /// Concurrent values of the register, each tagged with the clock of the write.
+    entries: Vec<(VClock, T)>,
+}
+
+impl<T: Clone + Eq> Default for MVRegister<T> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<T: Clone + Eq> MVRegister<T> {
+    /// ### New MVRegister
+    ///
+    /// Creates a new register without values.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ### Values
+    ///
+    /// Returns a copy of the concurrent values of the register.
+    ///
+    /// * Returns a single value if there are no conflicts.
+    pub fn values(&self) -> Vec<T> {
+        self.entries
+            .iter()
+            .map(|(_, value)| value.clone())
+            .collect()
+    }
+
+    /// ### Is conflicted
+    ///
+    /// Returns whether the register holds more than one concurrent value.
+    #[inline]
+    pub fn is_conflicted(&self) -> bool {
+        self.entries.len() > 1
+    }
+
+    /// ### Get clock
+    ///
+    /// Returns the join of the clocks of all values in the register.
+    pub fn get_clock(&self) -> VClock {
+        let mut clock = VClock::new(None);
+        for (entry_clock, _) in &self.entries {
+            clock.merge(entry_clock);
+        }
+        clock
+    }
+
+    /// ### Set
+    ///
+    /// Writes a new value, superseding all values observed so far.
+    ///
+    /// * `value` - New value of the register.
+    /// * `node_id` - ID of the current node.
+    pub fn set(&mut self, value: T, node_id: UID) {
+        let mut clock = self.get_clock();
+        clock.increment(node_id);
+        self.entries = vec![(clock, value)];
+    }
+
+    /// ### Merge
+    ///
+    /// Merges the current register with another one. Values whose clock is dominated by a value
+    /// of the other register are discarded, concurrent values are kept.
+    ///
+    /// * `other` - Reference to the register to be merged.
+    pub fn merge(&mut self, other: &Self) {
+        let is_dominated = |clock: &VClock, entries: &Vec<(VClock, T)>| {
+            entries
+                .iter()
+                .any(|(other_clock, _)| clock.happened_before(other_clock))
+        };
+
+        let mut entries: Vec<(VClock, T)> = self
+            .entries
+            .iter()
+            .filter(|(clock, _)| !is_dominated(clock, &other.entries))
+            .cloned()
+            .collect();
+        for (clock, value) in &other.entries {
+            let is_known = entries
+                .iter()
+                .any(|(current, _)| current.partial_cmp(clock) == Some(Equal));
+            if !is_known && !is_dominated(clock, &self.entries) {
+                entries.push((clock.clone(), value.clone()));
+            }
+        }
+        self.entries = entries;
+    }
+}
+
+//#region Serialization
+impl<T: Clone + Eq + VarSerialize> VarSerialize for MVRegister<T> {
+    fn serialize_var(&self) -> Vec<u8> {
+        let (clocks, values): (Vec<VClock>, Vec<T>) = self.entries.iter().cloned().unzip();
+        let mut encoded = clocks.serialize_var();
+        encoded.extend(values.serialize_var());
+        encoded
+    }
+}
+
+impl<T: Clone + Eq + VarDeserialize> VarDeserialize for MVRegister<T> {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (clocks, rest) = Vec::<VClock>::deserialize_var(encoded)?;
+        let (values, rest) = Vec::<T>::deserialize_var(rest)?;
+        if clocks.len() != values.len() {
+            return Err(DeserializeError::InvalidValue);
+        }
+        Ok((
+            Self {
+                entries: clocks.into_iter().zip(values).collect(),
+            },
+            rest,
+        ))
+    }
+}
+//#endregion
+
+#[cfg(test)]
+mod mvregister_tests {
+    use super::*;
+    use crate::serialization::test_var_serialization;
+
+    #[test]
+    fn concurrent_writes_are_kept() {
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut reg_a = MVRegister::new();
+        reg_a.set(1u8, nid_a);
+        let mut reg_b = reg_a.clone();
+
+        reg_a.set(2, nid_a);
+        reg_b.set(3, nid_b);
+
+        let mut merged_a = reg_a.clone();
+        merged_a.merge(&reg_b);
+        let mut merged_b = reg_b.clone();
+        merged_b.merge(&reg_a);
+
+        let mut values = merged_a.values();
+        values.sort();
+        assert_eq!(values, vec![2, 3], "Concurrent writes should be kept.");
+        assert!(merged_a.is_conflicted());
+
+        let mut values_b = merged_b.values();
+        values_b.sort();
+        assert_eq!(values, values_b, "Merge should be commutative.");
+    }
+
+    #[test]
+    fn later_writes_supersede_observed_values() {
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut reg_a = MVRegister::new();
+        let mut reg_b = MVRegister::new();
+        reg_a.set(1u8, nid_a);
+        reg_b.set(2, nid_b);
+        reg_a.merge(&reg_b);
+
+        reg_a.set(3, nid_a);
+        reg_b.merge(&reg_a);
+        assert_eq!(
+            reg_b.values(),
+            vec![3],
+            "Superseded values should be discarded."
+        );
+
+        reg_b.merge(&reg_a);
+        assert_eq!(reg_b.values(), vec![3], "Merge should be idempotent.");
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let mut reg_a = MVRegister::new();
+        let mut reg_b = MVRegister::new();
+        reg_a.set(true, UID::new());
+        reg_b.set(false, UID::new());
+        reg_a.merge(&reg_b);
+        test_var_serialization(reg_a);
+    }
+}
@@ -0,0 +1,240 @@
+//! # ORSet CRDT
+//!
+//! Implementation of an _add-wins observed-remove set_ CRDT.
+//!
+//! Every addition of an element is tagged with a unique [`CausalDot`] generated by the
+//! [`DotContext`] of the set. Removing an element only discards the dots that have been observed
+//! locally, so an addition that is concurrent to a removal keeps the element in the set after
+//! merging.
+//!
+//! Removed dots stay in the context, so that a merge with a replica that has not yet seen the
+//! removal does not bring the element back, without keeping tombstones for them.
+//!
+//! ### Usage
+//!
+//! ```rust
+//! use crdts::orset::ORSet;
+//! use crdts::uid::UID;
+//!
+//! let (nid_a, nid_b) = (UID::new(), UID::new());
+//!
+//! let mut set_a = ORSet::new();
+//! set_a.add(String::from("tag"), nid_a);
+//! let mut set_b = set_a.clone();
+//!
+//! // Concurrent removal on `a` and re-addition on `b`.
+//! set_a.remove(&String::from("tag"));
+//! set_b.add(String::from("tag"), nid_b);
+//!
+//! set_a.merge(&set_b);
+//! assert!(set_a.contains(&String::from("tag")), "Additions win over concurrent removals.");
+//! ```
+use crate::{
+    dotcontext::DotContext,
+    serialization::{DeserializeError, VarDeserialize, VarSerialize},
+    uid::UID,
+    vclock::CausalDot,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// ## ORSet
+///
+/// Add-wins observed-remove set over a generic type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ORSet<T: Ord + Clone> {
+    /// ### Entries
+    ///
+    /// Elements in the set together with the dots of the additions that are still alive.
+    entries: BTreeMap<T, BTreeSet<CausalDot>>,
+
+    /// ### Context
+    ///
+    /// Dots of all the additions seen by the set, including the removed ones.
+    context: DotContext,
+}
+
+impl<T: Ord + Clone> Default for ORSet<T> {
+    fn default() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            context: DotContext::new(),
+        }
+    }
+}
+
+impl<T: Ord + Clone> ORSet<T> {
+    /// ### New ORSet
+    ///
+    /// Creates a new empty set.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ### Add
+    ///
+    /// Adds an element to the set, tagging the addition with a new dot. The dots of the previous
+    /// additions of the element are observed, so they are replaced by the new one.
+    ///
+    /// * `value` - Element to add.
+    /// * `nid` - ID of the current node.
+    pub fn add(&mut self, value: T, nid: UID) {
+        let dot = self.context.next_dot(nid);
+        self.entries.insert(value, BTreeSet::from([dot]));
+    }
+
+    /// ### Remove
+    ///
+    /// Removes an element from the set. Only the additions observed so far are discarded.
+    ///
+    /// * `value` - Element to remove.
+    pub fn remove(&mut self, value: &T) {
+        self.entries.remove(value);
+    }
+
+    /// ### Contains
+    ///
+    /// Returns whether an element is in the set.
+    #[inline]
+    pub fn contains(&self, value: &T) -> bool {
+        self.entries.contains_key(value)
+    }
+
+    /// ### Iterate
+    ///
+    /// Returns an iterator over the elements of the set in ascending order.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.entries.keys()
+    }
+
+    /// ### Length
+    ///
+    /// Returns the number of elements in the set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// ### Is empty
+    ///
+    /// Returns whether the set has no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// ### Merge
+    ///
+    /// Merges the state of another set into the current one.
+    ///
+    /// * `other` - State of another set.
+    pub fn merge(&mut self, other: &Self) {
+        // A dot missing from one side was removed there if that side has seen it.
+        let empty = BTreeSet::new();
+        self.entries.retain(|value, dots| {
+            let other_dots = other.entries.get(value).unwrap_or(&empty);
+            dots.retain(|dot| other_dots.contains(dot) || !other.context.contains(dot));
+            !dots.is_empty()
+        });
+        for (value, other_dots) in &other.entries {
+            let unseen = other_dots
+                .iter()
+                .filter(|dot| !self.context.contains(dot))
+                .copied();
+            let dots = self.entries.entry(value.clone()).or_default();
+            dots.extend(unseen);
+            if dots.is_empty() {
+                self.entries.remove(value);
+            }
+        }
+        self.context.merge(&other.context);
+    }
+}
+
+//#region Serialization
+impl<T: Ord + Clone + VarSerialize> VarSerialize for ORSet<T> {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = self.entries.serialize_var();
+        encoded.extend(self.context.serialize_var());
+        encoded
+    }
+}
+
+impl<T: Ord + Clone + VarDeserialize> VarDeserialize for ORSet<T> {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (entries, rest) = BTreeMap::deserialize_var(encoded)?;
+        let (context, rest) = DotContext::deserialize_var(rest)?;
+        Ok((Self { entries, context }, rest))
+    }
+}
+//#endregion
+
+#[cfg(test)]
+mod orset_tests {
+    use super::*;
+    use crate::serialization::test_var_serialization;
+
+    #[test]
+    fn add_and_remove_work() {
+        let nid = UID::new();
+        let mut set = ORSet::new();
+
+        set.add(1u8, nid);
+        set.add(2u8, nid);
+        assert!(set.contains(&1) && set.contains(&2));
+
+        set.remove(&1);
+        assert!(
+            !set.contains(&1),
+            "Removed elements should not be in the set."
+        );
+        assert_eq!(set.iter().copied().collect::<Vec<_>>(), vec![2]);
+    }
+
+    #[test]
+    fn merge_keeps_concurrent_adds() {
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+
+        let mut set_a = ORSet::new();
+        set_a.add(1u8, nid_a);
+        let mut set_b = set_a.clone();
+
+        set_a.remove(&1);
+        set_b.add(1u8, nid_b);
+
+        let mut merged_a = set_a.clone();
+        merged_a.merge(&set_b);
+        let mut merged_b = set_b.clone();
+        merged_b.merge(&set_a);
+
+        assert!(merged_a.contains(&1), "Concurrent additions should win.");
+        assert_eq!(merged_a, merged_b, "Merge should be commutative.");
+    }
+
+    #[test]
+    fn merge_does_not_revive_removed_elements() {
+        let nid = UID::new();
+
+        let mut set_a = ORSet::new();
+        set_a.add(1u8, nid);
+        let set_b = set_a.clone();
+
+        set_a.remove(&1);
+        set_a.merge(&set_b);
+        assert!(
+            !set_a.contains(&1),
+            "Stale additions should not revive elements."
+        );
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let nid = UID::new();
+        let mut set = ORSet::new();
+        set.add(1u8, nid);
+        set.add(2u8, nid);
+        set.remove(&1);
+        test_var_serialization(set);
+    }
+}
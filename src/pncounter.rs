@@ -1,12 +1,22 @@
 /// # PNCounter CRDT
 ///
 /// Implementation of a _positive/negative counter_ CRDT
-use crate::gcounter::GCounter;
+///
+/// Like [`GCounter`], mutators return a delta that other replicas join with
+/// [`PNCounter::merge_delta`].
+///
+/// The value of the counter is signed, while the partial counts of each node are unsigned and
+/// saturate instead of overflowing.
+use crate::{
+    gcounter::GCounter,
+    serialization::{DeserializeError, VarDeserialize, VarSerialize},
+    uid::UID,
+};
 
 /// ## Positive/Negative Counter
 ///
 /// Representation of a positive/negative counter CRDT.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct PNCounter {
     pub positive: GCounter,
     pub negative: GCounter,
@@ -19,7 +29,7 @@ impl PNCounter {
     ///
     /// * `node_id` - If given, initializes the count for the current node (as 0),
     ///     both for the positive as well for the negative counter.
-    pub fn new(node_id: Option<&String>) -> Self {
+    pub fn new(node_id: Option<UID>) -> Self {
         Self {
             positive: GCounter::new(node_id),
             negative: GCounter::new(node_id),
@@ -28,23 +38,70 @@ impl PNCounter {
 
     /// ### Get PNCounter Value
     ///
-    /// Returns the total count among all nodes.
-    pub fn get_value(&self) -> u32 {
-        self.positive.get_value() - self.negative.get_value()
+    /// Returns the total count among all nodes, which is negative if the counter has been
+    /// decremented more than incremented. Values beyond the range of [`i64`] are clamped.
+    pub fn get_value(&self) -> i64 {
+        let total = |counter: &GCounter| -> i128 {
+            counter.state.values().map(|count| *count as i128).sum()
+        };
+        let value = total(&self.positive) - total(&self.negative);
+        value.clamp(i64::MIN as i128, i64::MAX as i128) as i64
     }
 
     /// ### Increment Counter
     ///
     /// Increments the counter by 1.
-    pub fn increment(&mut self, node_id: &String) -> () {
-        self.positive.increment(node_id);
+    ///
+    /// * Returns the delta of the operation.
+    pub fn increment(&mut self, node_id: UID) -> Self {
+        self.add(node_id, 1)
     }
 
     /// ### Decrement Counter
     ///
     /// Decrements the counter by 1.
-    pub fn decrement(&mut self, node_id: &String) -> () {
-        self.negative.increment(node_id);
+    ///
+    /// * Returns the delta of the operation.
+    pub fn decrement(&mut self, node_id: UID) -> Self {
+        self.sub(node_id, 1)
+    }
+
+    /// ### Add
+    ///
+    /// Increments the counter by an arbitrary amount.
+    ///
+    /// * `node_id` - ID of the current node.
+    /// * `amount` - Amount to add.
+    /// * Returns the delta of the operation.
+    pub fn add(&mut self, node_id: UID, amount: u64) -> Self {
+        Self {
+            positive: self.positive.increment_by(node_id, amount),
+            negative: GCounter::new(None),
+        }
+    }
+
+    /// ### Subtract
+    ///
+    /// Decrements the counter by an arbitrary amount.
+    ///
+    /// * `node_id` - ID of the current node.
+    /// * `amount` - Amount to subtract.
+    /// * Returns the delta of the operation.
+    pub fn sub(&mut self, node_id: UID, amount: u64) -> Self {
+        Self {
+            positive: GCounter::new(None),
+            negative: self.negative.increment_by(node_id, amount),
+        }
+    }
+
+    /// ### Merge delta
+    ///
+    /// Joins a delta returned by a mutator of another replica into the counter.
+    ///
+    /// * `delta` - Delta to join.
+    #[inline]
+    pub fn merge_delta(&mut self, delta: &Self) {
+        self.merge_from_state(delta);
     }
 
     /// ### Merge from state
@@ -57,3 +114,75 @@ impl PNCounter {
         self.negative.merge_from_state(&other_counter.negative);
     }
 }
+
+//#region Serialization
+impl VarSerialize for PNCounter {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = self.positive.serialize_var();
+        encoded.extend(self.negative.serialize_var());
+        encoded
+    }
+}
+
+impl VarDeserialize for PNCounter {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (positive, rest) = GCounter::deserialize_var(encoded)?;
+        let (negative, rest) = GCounter::deserialize_var(rest)?;
+        Ok((Self { positive, negative }, rest))
+    }
+}
+//#endregion
+
+#[cfg(test)]
+mod pncounter_tests {
+    use super::*;
+    use crate::serialization::test_var_serialization;
+
+    #[test]
+    fn deltas_converge_to_full_state() {
+        let nid = UID::new();
+        let mut counter_a = PNCounter::new(None);
+        let mut counter_b = PNCounter::new(None);
+
+        let deltas = [
+            counter_a.add(nid, 4),
+            counter_a.decrement(nid),
+            counter_a.increment(nid),
+        ];
+        for delta in deltas.iter().rev() {
+            counter_b.merge_delta(delta);
+        }
+        assert_eq!(counter_b, counter_a);
+        assert_eq!(counter_b.get_value(), 4);
+    }
+
+    #[test]
+    fn value_can_be_negative() {
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut counter = PNCounter::new(None);
+        counter.add(nid_a, 2);
+        counter.sub(nid_b, 5);
+        assert_eq!(counter.get_value(), -3);
+
+        counter.add(nid_a, u64::MAX);
+        counter.add(nid_b, u64::MAX);
+        assert_eq!(
+            counter.positive.state.get(&nid_a),
+            Some(&u64::MAX),
+            "Partial counts should saturate."
+        );
+        assert_eq!(counter.get_value(), i64::MAX);
+
+        counter.sub(nid_a, u64::MAX);
+        counter.sub(nid_b, u64::MAX);
+        assert_eq!(counter.get_value(), 0);
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let mut counter = PNCounter::new(None);
+        counter.add(UID::new(), 3);
+        counter.sub(UID::new(), 1);
+        test_var_serialization(counter);
+    }
+}
@@ -0,0 +1,309 @@
+//! # Position
+//!
+//! Fractional-index positions for items that users can reorder (e.g. by drag-and-drop).
+//!
+//! Items are sorted by their position, which is stored in a
+//! [`LWWRegister`](crate::lwwregister::LWWRegister). Moving an item writes a new position between
+//! the positions of its new neighbours, so the items do not need a full sequence CRDT.
+//!
+//! A position is a string of bytes, compared lexicographically (a prefix comes before its
+//! extensions). A new position is found by choosing a digit between the digits of the neighbours,
+//! appending more digits if they are adjacent. There is no room between two positions when the
+//! larger one only extends the smaller one with zeros (e.g. `[5]` and `[5, 0]`), in which case
+//! [`Position::between`] returns an error.
+//!
+//! ### Tie-breaking
+//!
+//! Every generated position ends with the [`UID`] of the node that generated it. Two nodes that
+//! concurrently insert between the same neighbours pick the same digits, but still get distinct
+//! positions, ordered by their node IDs.
+//!
+//! ### Serialization/Deserialization
+//!
+//! The compact encoding of a position ([`Position::as_bytes`]) is the string of bytes itself, so
+//! positions can be compared in encoded form directly, like [`UID`]s and
+//! [`Timestamp`](crate::time::timestamp::Timestamp)s. The variable-size encoding used to store a
+//! position in other structures prefixes it with its length.
+//!
+//! ### Usage
+//!
+//! ```rust
+//! use crdts::position::Position;
+//! use crdts::uid::UID;
+//!
+//! let nid = UID::new();
+//! let first = Position::first(nid);
+//! let last = first.after(nid);
+//! let middle = Position::between(Some(&first), Some(&last), nid).unwrap();
+//!
+//! assert!(first < middle && middle < last);
+//! assert!(first.as_bytes() < middle.as_bytes(), "Encoded positions compare the same way.");
+//! ```
+use crate::{
+    serialization::{DeserializeError, Serialize, VarDeserialize, VarSerialize, UID_SIZE},
+    uid::UID,
+};
+
+/// ## Position
+///
+/// Position of an item in a user-defined order.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Position(Vec<u8>);
+
+impl Position {
+    /// ### First position
+    ///
+    /// Returns a position for the first item of an empty list.
+    ///
+    /// * `nid` - ID of the current node.
+    #[inline]
+    pub fn first(nid: UID) -> Self {
+        Self::generate(None, None, nid)
+    }
+
+    /// ### Before
+    ///
+    /// Returns a position right before the current one.
+    ///
+    /// * `nid` - ID of the current node.
+    #[inline]
+    pub fn before(&self, nid: UID) -> Self {
+        Self::generate(None, Some(self), nid)
+    }
+
+    /// ### After
+    ///
+    /// Returns a position right after the current one.
+    ///
+    /// * `nid` - ID of the current node.
+    #[inline]
+    pub fn after(&self, nid: UID) -> Self {
+        Self::generate(Some(self), None, nid)
+    }
+
+    /// ### Between
+    ///
+    /// Returns a position strictly between two positions.
+    ///
+    /// * `before` - Position of the previous item, or `None` for the start of the list.
+    /// * `after` - Position of the next item, or `None` for the end of the list.
+    /// * `nid` - ID of the current node.
+    /// * Throws [`PositionError::InvalidOrder`] if `before` is not smaller than `after`.
+    /// * Throws [`PositionError::NoRoom`] if `after` only extends `before` with zeros.
+    pub fn between(
+        before: Option<&Position>,
+        after: Option<&Position>,
+        nid: UID,
+    ) -> Result<Self, PositionError> {
+        if let (Some(before), Some(after)) = (before, after) {
+            if before >= after {
+                return Err(PositionError::InvalidOrder);
+            }
+            let extension = after.as_bytes().strip_prefix(before.as_bytes());
+            if extension.is_some_and(|digits| digits.iter().all(|digit| *digit == 0)) {
+                return Err(PositionError::NoRoom);
+            }
+        }
+        Ok(Self::generate(before, after, nid))
+    }
+
+    /// ### From bytes
+    ///
+    /// Creates a position from its compact encoding.
+    ///
+    /// * `bytes` - Compact encoding of the position.
+    /// * Throws [`PositionError::InvalidPosition`] if the bytes cannot be a generated position,
+    ///   i.e. they are too short to end with a node ID, or they are all zeros so that no position
+    ///   comes before them.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PositionError> {
+        if bytes.len() <= UID_SIZE || bytes.iter().all(|digit| *digit == 0) {
+            return Err(PositionError::InvalidPosition);
+        }
+        Ok(Self(bytes.to_vec()))
+    }
+
+    /// ### As bytes
+    ///
+    /// Returns the compact encoding of the position, which compares like the position itself.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// ### Generate
+    ///
+    /// Builds the digits of a position between two ordered bounds and appends the node ID.
+    ///
+    /// The digits are built one by one. As long as the new position shares a prefix with a
+    /// bound, the digit of that bound at the current index limits the choice. As soon as there is
+    /// a free digit between the limits, it is picked and the new position no longer shares a
+    /// prefix with any bound, so appending the node ID keeps it between the bounds.
+    fn generate(before: Option<&Position>, after: Option<&Position>, nid: UID) -> Self {
+        let mut digits = Vec::new();
+        let mut lower = before.map(Position::as_bytes);
+        let mut upper = after.map(Position::as_bytes);
+        for index in 0.. {
+            // An exhausted lower bound is a prefix of the new position, so any digit is larger.
+            let lo = lower
+                .and_then(|bytes| bytes.get(index))
+                .map_or(-1, |digit| *digit as i16);
+            let hi = upper
+                .and_then(|bytes| bytes.get(index))
+                .map_or(256, |digit| *digit as i16);
+
+            if hi - lo > 1 {
+                digits.push(((lo + hi) / 2) as u8);
+                break;
+            } else if lo < 0 {
+                // The upper bound continues with a zero: follow it and keep looking.
+                digits.push(0);
+                lower = None;
+            } else {
+                digits.push(lo as u8);
+                if lo < hi {
+                    upper = None;
+                }
+            }
+        }
+        digits.extend(nid.serialize());
+        Self(digits)
+    }
+}
+
+//#region Serialization
+impl VarSerialize for Position {
+    fn serialize_var(&self) -> Vec<u8> {
+        self.0.serialize_var()
+    }
+}
+
+impl VarDeserialize for Position {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (bytes, rest) = Vec::<u8>::deserialize_var(encoded)?;
+        let position = Self::from_bytes(&bytes).map_err(|_| DeserializeError::InvalidValue)?;
+        Ok((position, rest))
+    }
+}
+//#endregion
+
+//#region PositionError
+/// ## Position error
+///
+/// Custom error related to position generation.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PositionError {
+    InvalidOrder,
+    NoRoom,
+    InvalidPosition,
+}
+//#endregion
+
+#[cfg(test)]
+mod position_tests {
+    use super::*;
+    use crate::lwwregister::LWWRegister;
+    use crate::serialization::test_var_serialization;
+    use crate::time::hlc::{HybridLogicalClock, SysTimeHLC};
+
+    #[test]
+    fn positions_are_generated_between_bounds() {
+        let nid = UID::new();
+        let first = Position::first(nid);
+        let mut lower = first.clone();
+        let mut upper = first.after(nid);
+        for i in 0..200 {
+            let middle = Position::between(Some(&lower), Some(&upper), nid).unwrap();
+            assert!(lower < middle && middle < upper);
+            assert_eq!(
+                lower.as_bytes().cmp(middle.as_bytes()),
+                lower.cmp(&middle),
+                "Encoded positions should compare like positions."
+            );
+            if i % 2 == 0 {
+                lower = middle;
+            } else {
+                upper = middle;
+            }
+        }
+
+        let mut head = first;
+        for _ in 0..200 {
+            let before = head.before(nid);
+            assert!(before < head);
+            head = before;
+        }
+
+        assert_eq!(
+            Position::between(Some(&upper), Some(&lower), nid),
+            Err(PositionError::InvalidOrder)
+        );
+    }
+
+    #[test]
+    fn positions_without_room_are_rejected() {
+        let nid = UID::new();
+        let mut lower = vec![5];
+        lower.extend(nid.serialize());
+        let mut upper = lower.clone();
+        upper.extend([0, 0]);
+        let (lower, upper) = (
+            Position::from_bytes(&lower).unwrap(),
+            Position::from_bytes(&upper).unwrap(),
+        );
+        assert_eq!(
+            Position::between(Some(&lower), Some(&upper), nid),
+            Err(PositionError::NoRoom)
+        );
+
+        let before = upper.before(nid);
+        assert!(before < upper);
+        let after = lower.after(nid);
+        assert!(lower < after);
+    }
+
+    #[test]
+    fn concurrent_positions_are_distinct() {
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let first = Position::first(nid_a);
+        let last = first.after(nid_a);
+
+        let pos_a = Position::between(Some(&first), Some(&last), nid_a).unwrap();
+        let pos_b = Position::between(Some(&first), Some(&last), nid_b).unwrap();
+        assert_ne!(pos_a, pos_b);
+        assert_eq!(pos_a < pos_b, nid_a < nid_b, "Ties are decided by node ID.");
+
+        let (low, high) = if pos_a < pos_b {
+            (&pos_a, &pos_b)
+        } else {
+            (&pos_b, &pos_a)
+        };
+        assert!(first < *low && *high < last);
+        let middle = Position::between(Some(low), Some(high), nid_a).unwrap();
+        assert!(*low < middle && middle < *high);
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let nid = UID::new();
+        let ts = SysTimeHLC::default().generate_timestamp();
+        let position = Position::first(nid);
+        assert_eq!(
+            Position::from_bytes(position.as_bytes()),
+            Ok(position.clone())
+        );
+        assert_eq!(
+            Position::from_bytes(&[]),
+            Err(PositionError::InvalidPosition)
+        );
+        assert_eq!(
+            Position::from_bytes(&[0; UID_SIZE + 1]),
+            Err(PositionError::InvalidPosition)
+        );
+        assert_eq!(
+            Position::deserialize_var(&Vec::<u8>::new().serialize_var()),
+            Err(DeserializeError::InvalidValue)
+        );
+        test_var_serialization(position.clone());
+        test_var_serialization(LWWRegister::new(ts, position));
+    }
+}
@@ -0,0 +1,187 @@
+//! # RCounter CRDT
+//!
+//! Implementation of a _resettable counter_ CRDT, e.g. for unread counts that are cleared when
+//! the user reads them.
+//!
+//! Every node keeps a grow-only count of its increments. A reset does not clear these counts;
+//! instead it records the increments it has observed in a [`VClock`], and the value of the
+//! counter only includes the increments beyond that clock. A reset therefore only cancels the
+//! increments it has observed: increments that are concurrent to a reset (or made after it)
+//! survive the merge.
+//!
+//! ### Usage
+//!
+//! ```rust
+//! use crdts::rcounter::RCounter;
+//! use crdts::uid::UID;
+//!
+//! let (nid_a, nid_b) = (UID::new(), UID::new());
+//!
+//! let mut unread_a = RCounter::new();
+//! unread_a.increment(nid_a, 3);
+//! let mut unread_b = unread_a.clone();
+//!
+//! // Concurrent reset on `a` and new message on `b`.
+//! unread_a.reset();
+//! unread_b.increment(nid_b, 1);
+//!
+//! unread_a.merge(&unread_b);
+//! assert_eq!(unread_a.get_value(), 1, "The concurrent increment survives the reset.");
+//! ```
+use crate::{
+    gcounter::GCounter,
+    serialization::{DeserializeError, VarDeserialize, VarSerialize},
+    uid::UID,
+    vclock::VClock,
+};
+
+/// ## Resettable Counter
+///
+/// Representation of a counter that can be reset to 0.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RCounter {
+    /// ### Increments
+    ///
+    /// Amount each node has added to the counter since its creation.
+    increments: GCounter,
+
+    /// ### Reset clock
+    ///
+    /// Increments of each node observed by the resets of the counter.
+    reset: VClock,
+}
+
+impl Default for RCounter {
+    fn default() -> Self {
+        Self {
+            increments: GCounter::new(None),
+            reset: VClock::new(None),
+        }
+    }
+}
+
+impl RCounter {
+    /// ### New RCounter
+    ///
+    /// Creates a new counter with value 0.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ### Get RCounter Value
+    ///
+    /// Returns the total count of the increments that have not been reset.
+    pub fn get_value(&self) -> u64 {
+        self.increments
+            .state
+            .iter()
+            .map(|(node_id, count)| count.saturating_sub(self.reset.get(*node_id)))
+            .fold(0, |total, count| total.saturating_add(count))
+    }
+
+    /// ### Increment Counter
+    ///
+    /// Increments the counter.
+    ///
+    /// * `node_id` - ID of the current node.
+    /// * `amount` - Amount to add.
+    #[inline]
+    pub fn increment(&mut self, node_id: UID, amount: u64) {
+        self.increments.increment_by(node_id, amount);
+    }
+
+    /// ### Reset Counter
+    ///
+    /// Resets the counter to 0, cancelling all the increments observed so far.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.reset.merge(&VClock::from(self.increments.clone()));
+    }
+
+    /// ### Merge
+    ///
+    /// Merges the state of another counter into the current one.
+    ///
+    /// * `other` - State of another counter.
+    pub fn merge(&mut self, other: &Self) {
+        self.increments.merge_from_state(&other.increments);
+        self.reset.merge(&other.reset);
+    }
+}
+
+//#region Serialization
+impl VarSerialize for RCounter {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = self.increments.serialize_var();
+        encoded.extend(self.reset.serialize_var());
+        encoded
+    }
+}
+
+impl VarDeserialize for RCounter {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (increments, rest) = GCounter::deserialize_var(encoded)?;
+        let (reset, rest) = VClock::deserialize_var(rest)?;
+        Ok((Self { increments, reset }, rest))
+    }
+}
+//#endregion
+
+#[cfg(test)]
+mod rcounter_tests {
+    use super::*;
+    use crate::serialization::test_var_serialization;
+
+    #[test]
+    fn reset_cancels_observed_increments_only() {
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut counter_a = RCounter::new();
+        counter_a.increment(nid_a, 2);
+        let mut counter_b = counter_a.clone();
+
+        counter_a.increment(nid_a, 1);
+        counter_a.reset();
+        counter_a.increment(nid_a, 4);
+        assert_eq!(
+            counter_a.get_value(),
+            4,
+            "Increments after the reset count."
+        );
+
+        counter_b.increment(nid_b, 5);
+
+        let mut merged_b = counter_b.clone();
+        merged_b.merge(&counter_a);
+        counter_a.merge(&counter_b);
+
+        assert_eq!(counter_a, merged_b, "Merge should be commutative.");
+        assert_eq!(counter_a.get_value(), 9);
+
+        counter_a.reset();
+        assert_eq!(counter_a.get_value(), 0);
+    }
+
+    #[test]
+    fn counts_saturate() {
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut counter = RCounter::new();
+        counter.increment(nid_a, u64::MAX);
+        counter.increment(nid_b, 1);
+        assert_eq!(counter.get_value(), u64::MAX);
+
+        let mut stale = RCounter::new();
+        stale.increment(nid_a, 1);
+        stale.reset = VClock::from(counter.increments.clone());
+        assert_eq!(stale.get_value(), 0, "Resets should not underflow.");
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let mut counter = RCounter::new();
+        counter.increment(UID::new(), 3);
+        counter.reset();
+        counter.increment(UID::new(), 1);
+        test_var_serialization(counter);
+    }
+}
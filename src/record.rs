@@ -0,0 +1,467 @@
+//! # Record CRDT
+//!
+//! Structs whose fields merge independently: every field is a last-write-wins register (a
+//! [`RecordField`]), so concurrent updates of different fields are all kept, and concurrent
+//! updates of the same field are decided by timestamp (and node ID on ties).
+//!
+//! Record types are declared with the [`record!`](crate::record!) macro, which wraps the type of
+//! every field in a [`RecordField`] and implements the [`Record`] trait and the variable-size
+//! serialization traits for the struct.
+//!
+//! ### Patches
+//!
+//! Instead of sending the whole record (or one message per field) after an update,
+//! [`Record::update`] returns a [`RecordPatch`] holding only the fields that changed. Applying a
+//! patch merges these fields into another replica. Fields of a patch are identified by name and
+//! unknown fields are ignored, so replicas with an older version of a record type can still
+//! apply patches of newer versions.
+//!
+//! ### Usage
+//!
+//! ```rust
+//! use crdts::record;
+//! use crdts::record::Record;
+//! use crdts::time::hlc::{HybridLogicalClock, SysTimeHLC};
+//! use crdts::uid::UID;
+//!
+//! record! {
+//!     #[derive(Clone, Debug, PartialEq)]
+//!     pub struct Todo {
+//!         pub title: String,
+//!         pub done: bool,
+//!     }
+//! }
+//!
+//! let mut hlc = SysTimeHLC::default();
+//! let (nid_a, nid_b) = (UID::new(), UID::new());
+//!
+//! let mut todo_a = Todo::new(String::from("Milk"), false, nid_a, hlc.generate_timestamp());
+//! let mut todo_b = todo_a.clone();
+//!
+//! let patch_a = todo_a.update(|todo| todo.done.set(true, nid_a, hlc.generate_timestamp()));
+//! let patch_b = todo_b.update(|todo| {
+//!     todo.title.set(String::from("Oat milk"), nid_b, hlc.generate_timestamp())
+//! });
+//! assert_eq!(patch_a.len(), 1, "Only the changed field is sent.");
+//!
+//! todo_a.apply(&patch_b).unwrap();
+//! todo_b.apply(&patch_a).unwrap();
+//! assert_eq!(todo_a, todo_b);
+//! assert_eq!(todo_a.title.get_value(), "Oat milk");
+//! assert!(todo_a.done.get_value());
+//! ```
+use crate::{
+    lwwregister::LWWRegister,
+    serialization::{DeserializeError, VarDeserialize, VarSerialize},
+    time::timestamp::Timestamp,
+    uid::UID,
+};
+use std::collections::BTreeMap;
+
+//#region RecordField
+/// ## Record field
+///
+/// Field of a record: a register together with the ID of the node that performed the last write.
+#[derive(Clone, Default, Debug, Eq, PartialEq)]
+pub struct RecordField<T: Clone> {
+    /// ### Register
+    ///
+    /// Register holding the last written value and its timestamp.
+    register: LWWRegister<T>,
+
+    /// ### Node ID
+    ///
+    /// ID of the node that performed the last write.
+    nid: UID,
+}
+
+impl<T: Clone> RecordField<T> {
+    /// ### New field
+    ///
+    /// Creates a field holding a value written by node `nid` at time `ts`.
+    #[inline]
+    pub fn new(value: T, nid: UID, ts: Timestamp) -> Self {
+        Self {
+            register: LWWRegister::new(ts, value),
+            nid,
+        }
+    }
+
+    /// ### Get value
+    ///
+    /// Returns a copy of the value of the field.
+    #[inline]
+    pub fn get_value(&self) -> T {
+        self.register.get_value()
+    }
+
+    /// ### Get timestamp
+    ///
+    /// Returns the timestamp of the last write.
+    #[inline]
+    pub fn get_timestamp(&self) -> Timestamp {
+        self.register.get_timestamp()
+    }
+
+    /// ### Get node ID
+    ///
+    /// Returns the ID of the node that performed the last write.
+    #[inline]
+    pub fn get_nid(&self) -> UID {
+        self.nid
+    }
+
+    /// ### Set
+    ///
+    /// Writes a value. The write only takes effect if it is newer than the current one.
+    ///
+    /// * `value` - Value to write.
+    /// * `nid` - ID of the current node.
+    /// * `ts` - Timestamp of the operation.
+    pub fn set(&mut self, value: T, nid: UID, ts: Timestamp) {
+        self.merge(&Self::new(value, nid, ts));
+    }
+
+    /// ### Merge
+    ///
+    /// Merges another version of the field. The write with the largest timestamp wins. Tied
+    /// cases are decided by the order of the node IDs.
+    ///
+    /// * `other` - Field to be merged.
+    pub fn merge(&mut self, other: &Self) {
+        if (self.get_timestamp(), self.nid) < (other.get_timestamp(), other.nid) {
+            self.register.merge(&other.register, self.nid, other.nid);
+            self.nid = other.nid;
+        }
+    }
+}
+
+impl<T: Clone + VarSerialize> VarSerialize for RecordField<T> {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = self.register.serialize_var();
+        encoded.extend(self.nid.serialize_var());
+        encoded
+    }
+}
+
+impl<T: Clone + VarDeserialize> VarDeserialize for RecordField<T> {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (register, rest) = LWWRegister::deserialize_var(encoded)?;
+        let (nid, rest) = UID::deserialize_var(rest)?;
+        Ok((Self { register, nid }, rest))
+    }
+}
+//#endregion
+
+//#region RecordPatch
+/// ## Record patch
+///
+/// Partial update of a record: the encoded versions of the fields that changed, by field name.
+#[derive(Clone, Default, Debug, Eq, PartialEq)]
+pub struct RecordPatch {
+    /// ### Fields
+    ///
+    /// Encoded [`RecordField`]s by field name.
+    fields: BTreeMap<String, Vec<u8>>,
+}
+
+impl RecordPatch {
+    /// ### New patch
+    ///
+    /// Creates an empty patch.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ### Insert
+    ///
+    /// Adds a field to the patch, replacing any previous version of the field.
+    pub fn insert<T: Clone + VarSerialize>(&mut self, name: &str, field: &RecordField<T>) {
+        self.fields.insert(name.to_string(), field.serialize_var());
+    }
+
+    /// ### Get
+    ///
+    /// Decodes a field of the patch.
+    ///
+    /// * Returns `None` if the patch does not include the field.
+    /// * Throws [`DeserializeError`] if the field cannot be decoded as a field of type `T`.
+    pub fn get<T: Clone + VarDeserialize>(
+        &self,
+        name: &str,
+    ) -> Result<Option<RecordField<T>>, DeserializeError> {
+        self.fields
+            .get(name)
+            .map(|encoded| RecordField::deserialize_var(encoded).map(|(field, _)| field))
+            .transpose()
+    }
+
+    /// ### Field names
+    ///
+    /// Returns an iterator over the names of the fields in the patch.
+    pub fn field_names(&self) -> impl Iterator<Item = &str> {
+        self.fields.keys().map(String::as_str)
+    }
+
+    /// ### Length
+    ///
+    /// Returns the number of fields in the patch.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// ### Is empty
+    ///
+    /// Returns whether the patch does not change any field.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+impl VarSerialize for RecordPatch {
+    fn serialize_var(&self) -> Vec<u8> {
+        self.fields.serialize_var()
+    }
+}
+
+impl VarDeserialize for RecordPatch {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (fields, rest) = BTreeMap::deserialize_var(encoded)?;
+        Ok((Self { fields }, rest))
+    }
+}
+//#endregion
+
+//#region Record
+/// ## Record
+///
+/// Struct whose fields are [`RecordField`]s that merge independently. Implemented by the
+/// [`record!`](crate::record!) macro.
+pub trait Record: Clone {
+    /// ### Diff
+    ///
+    /// Returns a patch with the fields that have been written since a previous version of the
+    /// record.
+    fn diff(&self, since: &Self) -> RecordPatch;
+
+    /// ### Apply
+    ///
+    /// Merges the fields of a patch into the record. Fields unknown to the record are ignored.
+    ///
+    /// * Throws [`DeserializeError`] if a known field cannot be decoded. The record is left
+    ///   unchanged.
+    fn apply(&mut self, patch: &RecordPatch) -> Result<(), DeserializeError>;
+
+    /// ### Merge
+    ///
+    /// Merges the state of another record into the current one, field by field.
+    fn merge(&mut self, other: &Self);
+
+    /// ### Update
+    ///
+    /// Runs a function setting fields of the record and returns the patch of the fields it
+    /// changed.
+    fn update(&mut self, f: impl FnOnce(&mut Self)) -> RecordPatch {
+        let before = self.clone();
+        f(self);
+        self.diff(&before)
+    }
+}
+
+/// ## Record
+///
+/// Declares a struct implementing [`Record`]. The type of every field is wrapped in a
+/// [`RecordField`], and a `new` constructor taking the initial value of every field (followed by
+/// the node ID and timestamp of the initial write) is generated.
+///
+/// Field types must implement `Clone`, [`VarSerialize`] and [`VarDeserialize`].
+#[macro_export]
+macro_rules! record {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($(#[$field_meta:meta])* $field_vis:vis $field:ident : $t:ty),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $($(#[$field_meta])* $field_vis $field: $crate::record::RecordField<$t>,)*
+        }
+
+        impl $name {
+            /// ### New record
+            ///
+            /// Creates a record whose fields were all written by node `nid` at time `ts`.
+            #[allow(clippy::too_many_arguments)]
+            pub fn new(
+                $($field: $t,)*
+                nid: $crate::uid::UID,
+                ts: $crate::time::timestamp::Timestamp,
+            ) -> Self {
+                Self {
+                    $($field: $crate::record::RecordField::new($field, nid, ts),)*
+                }
+            }
+        }
+
+        impl $crate::record::Record for $name {
+            fn diff(&self, since: &Self) -> $crate::record::RecordPatch {
+                let mut patch = $crate::record::RecordPatch::new();
+                $(
+                    if (self.$field.get_timestamp(), self.$field.get_nid())
+                        != (since.$field.get_timestamp(), since.$field.get_nid())
+                    {
+                        patch.insert(stringify!($field), &self.$field);
+                    }
+                )*
+                patch
+            }
+
+            fn apply(
+                &mut self,
+                patch: &$crate::record::RecordPatch,
+            ) -> Result<(), $crate::serialization::DeserializeError> {
+                // Decode all fields first, so that an invalid patch leaves the record unchanged.
+                $(let $field = patch.get::<$t>(stringify!($field))?;)*
+                $(
+                    if let Some(field) = $field {
+                        self.$field.merge(&field);
+                    }
+                )*
+                Ok(())
+            }
+
+            fn merge(&mut self, other: &Self) {
+                $(self.$field.merge(&other.$field);)*
+            }
+        }
+
+        impl $crate::serialization::VarSerialize for $name {
+            fn serialize_var(&self) -> Vec<u8> {
+                let mut encoded = Vec::new();
+                $(encoded.extend($crate::serialization::VarSerialize::serialize_var(&self.$field));)*
+                encoded
+            }
+        }
+
+        impl $crate::serialization::VarDeserialize for $name {
+            fn deserialize_var(
+                encoded: &[u8],
+            ) -> Result<(Self, &[u8]), $crate::serialization::DeserializeError> {
+                let rest = encoded;
+                $(
+                    let ($field, rest) =
+                        <$crate::record::RecordField<$t> as $crate::serialization::VarDeserialize>::deserialize_var(rest)?;
+                )*
+                Ok((Self { $($field,)* }, rest))
+            }
+        }
+    };
+}
+//#endregion
+
+#[cfg(test)]
+mod record_tests {
+    use super::*;
+    use crate::serialization::test_var_serialization;
+    use crate::time::hlc::{HybridLogicalClock, SysTimeHLC};
+
+    record! {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Profile {
+            name: String,
+            age: i64,
+            avatar: Vec<u8>,
+            public: bool,
+        }
+    }
+
+    fn profile(nid: UID, ts: Timestamp) -> Profile {
+        Profile::new(String::from("Ada"), 36, vec![1, 2], false, nid, ts)
+    }
+
+    #[test]
+    fn patches_include_only_changed_fields() {
+        let mut hlc = SysTimeHLC::default();
+        let nid = UID::new();
+        let mut profile_a = profile(nid, hlc.generate_timestamp());
+        let mut profile_b = profile_a.clone();
+
+        let patch = profile_a.update(|p| {
+            p.age.set(37, nid, hlc.generate_timestamp());
+            p.public.set(true, nid, hlc.generate_timestamp());
+        });
+        assert_eq!(
+            patch.field_names().collect::<Vec<_>>(),
+            vec!["age", "public"]
+        );
+        assert!(profile_a.update(|_| {}).is_empty());
+
+        profile_b.apply(&patch).unwrap();
+        assert_eq!(profile_a, profile_b);
+
+        let mut invalid = RecordPatch::new();
+        invalid.insert(
+            "age",
+            &RecordField::new(true, nid, hlc.generate_timestamp()),
+        );
+        invalid.insert(
+            "name",
+            &RecordField::new(String::new(), nid, hlc.generate_timestamp()),
+        );
+        assert!(profile_b.apply(&invalid).is_err());
+        assert_eq!(
+            profile_a, profile_b,
+            "Invalid patches should not be applied."
+        );
+    }
+
+    #[test]
+    fn fields_merge_independently() {
+        let mut hlc = SysTimeHLC::default();
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut profile_a = profile(nid_a, hlc.generate_timestamp());
+        let mut profile_b = profile_a.clone();
+
+        profile_a
+            .name
+            .set(String::from("Ada L."), nid_a, hlc.generate_timestamp());
+        let ts = hlc.generate_timestamp();
+        profile_a.age.set(40, nid_a, ts);
+        profile_b.age.set(41, nid_b, ts);
+        profile_b
+            .avatar
+            .set(Vec::new(), nid_b, hlc.generate_timestamp());
+
+        let mut merged_b = profile_b.clone();
+        merged_b.merge(&profile_a);
+        profile_a.merge(&profile_b);
+        assert_eq!(profile_a, merged_b, "Merge should be commutative.");
+
+        assert_eq!(profile_a.name.get_value(), "Ada L.");
+        assert!(profile_a.avatar.get_value().is_empty());
+        let winner = if nid_a < nid_b { 41 } else { 40 };
+        assert_eq!(
+            profile_a.age.get_value(),
+            winner,
+            "Ties are decided by node ID."
+        );
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let mut hlc = SysTimeHLC::default();
+        let nid = UID::new();
+        let mut profile = profile(nid, hlc.generate_timestamp());
+        let patch = profile.update(|p| {
+            p.name
+                .set(String::from("Bo"), nid, hlc.generate_timestamp())
+        });
+
+        test_var_serialization(patch);
+        test_var_serialization(profile);
+    }
+}
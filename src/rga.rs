@@ -0,0 +1,591 @@
+//! # RGA CRDT
+//!
+//! Implementation of a _replicated growable array (RGA)_, a sequence CRDT for ordered lists.
+//!
+//! Every element is identified by a [`Dot`] built from the ID of the node that inserted it and
+//! the HLC timestamp of the insertion. An element is inserted right after another element (its
+//! _origin_), or at the head of the sequence. Concurrent insertions after the same origin are
+//! ordered by descending ID, so that all replicas converge to the same order.
+//!
+//! Deleted elements are kept as tombstones, since they may still be referenced as origins by
+//! operations that have not been received yet.
+//!
+//! **Causality.** The integration algorithm relies on HLC timestamps tracking causality: an
+//! element must always have a larger ID than its origin. This is the case as long as nodes update
+//! their HLCs with the timestamps of the operations they receive.
+//!
+//! Reference: [Replicated abstract data types - Roh et. al.](https://doi.org/10.1016/j.jpdc.2010.12.006)
+//!
+//! ### Usage
+//!
+//! ```rust
+//! use crdts::rga::RGA;
+//! use crdts::time::hlc::{HybridLogicalClock, SysTimeHLC};
+//! use crdts::uid::UID;
+//!
+//! let mut hlc = SysTimeHLC::default();
+//! let nid = UID::new();
+//!
+//! let mut list_a = RGA::new();
+//! let mut list_b = RGA::new();
+//! let op = list_a.insert_at(0, 'a', nid, hlc.generate_timestamp()).unwrap();
+//! list_b.apply(&op).unwrap();
+//!
+//! assert_eq!(list_b.iter().collect::<String>(), "a");
+//! ```
+use crate::{
+    dot::Dot,
+    serialization::{DeserializeError, VarDeserialize, VarSerialize},
+    time::timestamp::Timestamp,
+    uid::UID,
+};
+use std::collections::BTreeSet;
+
+//#region Operations
+/// ## RGA operation
+///
+/// Operation generated by a local update that can be sent to other replicas.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RGAOp<T> {
+    /// ### Insert
+    ///
+    /// Inserts an element with ID `id` right after the element `origin` (or at the head).
+    Insert {
+        id: Dot,
+        origin: Option<Dot>,
+        value: T,
+    },
+
+    /// ### Delete
+    ///
+    /// Deletes the element with ID `id`.
+    Delete { id: Dot },
+}
+
+impl<T: VarSerialize> VarSerialize for RGAOp<T> {
+    fn serialize_var(&self) -> Vec<u8> {
+        match self {
+            RGAOp::Insert { id, origin, value } => {
+                let mut encoded = vec![0u8];
+                encoded.extend(id.serialize_var());
+                encoded.extend(origin.serialize_var());
+                encoded.extend(value.serialize_var());
+                encoded
+            }
+            RGAOp::Delete { id } => {
+                let mut encoded = vec![1u8];
+                encoded.extend(id.serialize_var());
+                encoded
+            }
+        }
+    }
+}
+
+impl<T: VarDeserialize> VarDeserialize for RGAOp<T> {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (kind, rest) = u8::deserialize_var(encoded)?;
+        let (id, rest) = Dot::deserialize_var(rest)?;
+        match kind {
+            0 => {
+                let (origin, rest) = Option::<Dot>::deserialize_var(rest)?;
+                let (value, rest) = T::deserialize_var(rest)?;
+                Ok((RGAOp::Insert { id, origin, value }, rest))
+            }
+            1 => Ok((RGAOp::Delete { id }, rest)),
+            _ => Err(DeserializeError::InvalidValue),
+        }
+    }
+}
+//#endregion
+
+//#region Element
+/// ## RGA element
+///
+/// Element of the sequence together with its metadata.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct RGAElement<T> {
+    /// ### ID
+    ///
+    /// Unique ID of the element.
+    id: Dot,
+
+    /// ### Origin
+    ///
+    /// ID of the element after which this element was inserted (`None` for the head).
+    origin: Option<Dot>,
+
+    /// ### Value
+    ///
+    /// Value of the element.
+    value: T,
+
+    /// ### Deleted
+    ///
+    /// Whether the element has been deleted (tombstone).
+    deleted: bool,
+}
+
+impl<T: VarSerialize> VarSerialize for RGAElement<T> {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = self.id.serialize_var();
+        encoded.extend(self.origin.serialize_var());
+        encoded.extend(self.value.serialize_var());
+        encoded.extend(self.deleted.serialize_var());
+        encoded
+    }
+}
+
+impl<T: VarDeserialize> VarDeserialize for RGAElement<T> {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (id, rest) = Dot::deserialize_var(encoded)?;
+        let (origin, rest) = Option::<Dot>::deserialize_var(rest)?;
+        let (value, rest) = T::deserialize_var(rest)?;
+        let (deleted, rest) = bool::deserialize_var(rest)?;
+        Ok((
+            Self {
+                id,
+                origin,
+                value,
+                deleted,
+            },
+            rest,
+        ))
+    }
+}
+//#endregion
+
+/// ## RGA
+///
+/// Replicated sequence of elements of a generic type.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RGA<T: Clone> {
+    /// ### Elements
+    ///
+    /// All elements of the sequence in document order, including tombstones.
+    elements: Vec<RGAElement<T>>,
+}
+
+impl<T: Clone> Default for RGA<T> {
+    fn default() -> Self {
+        Self {
+            elements: Vec::new(),
+        }
+    }
+}
+
+impl<T: Clone> RGA<T> {
+    /// ### New RGA
+    ///
+    /// Creates a new empty sequence.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ### Length
+    ///
+    /// Returns the number of visible (non-deleted) elements.
+    pub fn len(&self) -> usize {
+        self.elements.iter().filter(|e| !e.deleted).count()
+    }
+
+    /// ### Is empty
+    ///
+    /// Returns whether the sequence has no visible elements.
+    pub fn is_empty(&self) -> bool {
+        self.elements.iter().all(|e| e.deleted)
+    }
+
+    /// ### Iterate
+    ///
+    /// Returns an iterator over the visible values in document order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.elements
+            .iter()
+            .filter(|e| !e.deleted)
+            .map(|e| &e.value)
+    }
+
+    /// ### Iterate with tombstones
+    ///
+    /// Returns an iterator over the IDs of all elements in document order (including tombstones),
+    /// together with their values if they are visible.
+    pub fn iter_ids(&self) -> impl Iterator<Item = (Dot, Option<&T>)> {
+        self.elements
+            .iter()
+            .map(|e| (e.id, if e.deleted { None } else { Some(&e.value) }))
+    }
+
+    /// ### Get
+    ///
+    /// Returns the visible value at a given index.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.iter().nth(index)
+    }
+
+    /// ### Get mutable by ID
+    ///
+    /// Returns a mutable reference to the value of a visible element.
+    pub fn get_mut_by_id(&mut self, id: &Dot) -> Option<&mut T> {
+        self.elements
+            .iter_mut()
+            .find(|e| e.id == *id && !e.deleted)
+            .map(|e| &mut e.value)
+    }
+
+    /// ### ID at index
+    ///
+    /// Returns the ID of the visible element at a given index.
+    pub fn id_at(&self, index: usize) -> Option<Dot> {
+        self.elements
+            .iter()
+            .filter(|e| !e.deleted)
+            .nth(index)
+            .map(|e| e.id)
+    }
+
+    /// ### Contains
+    ///
+    /// Returns whether an element (visible or deleted) is known to the sequence.
+    pub fn contains(&self, id: &Dot) -> bool {
+        self.position(id).is_some()
+    }
+
+    /// ### Index of ID
+    ///
+    /// Returns the visible index of an element, or `None` if it is unknown or deleted.
+    pub fn index_of(&self, id: &Dot) -> Option<usize> {
+        let position = self.position(id)?;
+        if self.elements[position].deleted {
+            return None;
+        }
+        Some(
+            self.elements[..position]
+                .iter()
+                .filter(|e| !e.deleted)
+                .count(),
+        )
+    }
+
+    /// ### Insert after
+    ///
+    /// Inserts a value right after a given element.
+    ///
+    /// * `origin` - ID of the element to insert after, `None` to insert at the head.
+    /// * `value` - Value to insert.
+    /// * `nid` - ID of the current node.
+    /// * `ts` - Timestamp of the operation.
+    /// * Returns the operation to be sent to other replicas.
+    /// * Throws [`RGAError::UnknownElement`] if the origin is not in the sequence.
+    pub fn insert_after(
+        &mut self,
+        origin: Option<Dot>,
+        value: T,
+        nid: UID,
+        ts: Timestamp,
+    ) -> Result<RGAOp<T>, RGAError> {
+        let op = RGAOp::Insert {
+            id: Dot::new(nid, ts),
+            origin,
+            value,
+        };
+        self.apply(&op)?;
+        Ok(op)
+    }
+
+    /// ### Insert at index
+    ///
+    /// Inserts a value so that it ends up at a given visible index.
+    ///
+    /// * Throws [`RGAError::IndexOutOfBounds`] if the index is larger than the length.
+    pub fn insert_at(
+        &mut self,
+        index: usize,
+        value: T,
+        nid: UID,
+        ts: Timestamp,
+    ) -> Result<RGAOp<T>, RGAError> {
+        let origin = match index {
+            0 => None,
+            _ => Some(self.id_at(index - 1).ok_or(RGAError::IndexOutOfBounds)?),
+        };
+        self.insert_after(origin, value, nid, ts)
+    }
+
+    /// ### Delete
+    ///
+    /// Deletes an element, keeping it as a tombstone.
+    ///
+    /// * `id` - ID of the element to delete.
+    /// * Returns the operation to be sent to other replicas.
+    /// * Throws [`RGAError::UnknownElement`] if the element is not in the sequence.
+    pub fn delete(&mut self, id: Dot) -> Result<RGAOp<T>, RGAError> {
+        let op = RGAOp::Delete { id };
+        self.apply(&op)?;
+        Ok(op)
+    }
+
+    /// ### Delete at index
+    ///
+    /// Deletes the visible element at a given index.
+    ///
+    /// * Throws [`RGAError::IndexOutOfBounds`] if there is no element at the index.
+    pub fn delete_at(&mut self, index: usize) -> Result<RGAOp<T>, RGAError> {
+        let id = self.id_at(index).ok_or(RGAError::IndexOutOfBounds)?;
+        self.delete(id)
+    }
+
+    /// ### Apply
+    ///
+    /// Applies an operation (usually received from another replica). Applying the same operation
+    /// more than once has no further effect.
+    ///
+    /// * Throws [`RGAError::UnknownElement`] if the operation references an unknown element.
+    pub fn apply(&mut self, op: &RGAOp<T>) -> Result<(), RGAError> {
+        match op {
+            RGAOp::Insert { id, origin, value } => {
+                self.integrate(*id, *origin, value)?;
+            }
+            RGAOp::Delete { id } => {
+                let position = self.position(id).ok_or(RGAError::UnknownElement)?;
+                self.elements[position].deleted = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// ### Merge
+    ///
+    /// Merges the state of another sequence into the current one.
+    ///
+    /// * `other` - State of another sequence.
+    /// * Throws [`RGAError::UnknownElement`] if an element of the other sequence has an unknown
+    ///   origin, in which case the sequence is left unchanged.
+    pub fn merge(&mut self, other: &Self) -> Result<(), RGAError> {
+        // Elements always come after their origin in document order, so the origins are known by
+        // the time each element gets integrated. States received from other replicas are checked
+        // before changing anything.
+        let mut known = BTreeSet::new();
+        for element in &other.elements {
+            let is_known = element
+                .origin
+                .is_none_or(|origin| known.contains(&origin) || self.contains(&origin));
+            if !is_known {
+                return Err(RGAError::UnknownElement);
+            }
+            known.insert(element.id);
+        }
+
+        for element in &other.elements {
+            let position = self.integrate(element.id, element.origin, &element.value)?;
+            if element.deleted {
+                self.elements[position].deleted = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// ### Position
+    ///
+    /// Returns the position of an element among all elements (including tombstones).
+    fn position(&self, id: &Dot) -> Option<usize> {
+        self.elements.iter().position(|e| e.id == *id)
+    }
+
+    /// ### Integrate
+    ///
+    /// Inserts an element at its position according to the RGA ordering.
+    ///
+    /// * Returns the position of the element among all elements (including tombstones).
+    fn integrate(&mut self, id: Dot, origin: Option<Dot>, value: &T) -> Result<usize, RGAError> {
+        if let Some(position) = self.position(&id) {
+            return Ok(position);
+        }
+        let mut position = match origin {
+            None => 0,
+            Some(origin) => self.position(&origin).ok_or(RGAError::UnknownElement)? + 1,
+        };
+        // Skip concurrent insertions with higher priority, along with their descendants.
+        while position < self.elements.len() && self.elements[position].id > id {
+            position += 1;
+        }
+        self.elements.insert(
+            position,
+            RGAElement {
+                id,
+                origin,
+                value: value.clone(),
+                deleted: false,
+            },
+        );
+        Ok(position)
+    }
+}
+
+//#region Serialization
+impl<T: Clone + VarSerialize> VarSerialize for RGA<T> {
+    fn serialize_var(&self) -> Vec<u8> {
+        self.elements.serialize_var()
+    }
+}
+
+impl<T: Clone + VarDeserialize> VarDeserialize for RGA<T> {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (elements, rest) = Vec::deserialize_var(encoded)?;
+        Ok((Self { elements }, rest))
+    }
+}
+//#endregion
+
+//#region RGAError
+/// ## RGA error
+///
+/// Custom error related to sequence operations.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RGAError {
+    IndexOutOfBounds,
+    UnknownElement,
+}
+//#endregion
+
+#[cfg(test)]
+mod rga_tests {
+    use super::*;
+    use crate::serialization::test_var_serialization;
+    use crate::time::hlc::{HybridLogicalClock, SysTimeHLC};
+
+    fn to_vec(rga: &RGA<u8>) -> Vec<u8> {
+        rga.iter().copied().collect()
+    }
+
+    #[test]
+    fn insert_and_delete_work() {
+        let mut hlc = SysTimeHLC::default();
+        let nid = UID::new();
+        let mut rga = RGA::new();
+
+        rga.insert_at(0, 1u8, nid, hlc.generate_timestamp())
+            .unwrap();
+        rga.insert_at(1, 3, nid, hlc.generate_timestamp()).unwrap();
+        rga.insert_at(1, 2, nid, hlc.generate_timestamp()).unwrap();
+        assert_eq!(to_vec(&rga), vec![1, 2, 3]);
+
+        rga.delete_at(1).unwrap();
+        assert_eq!(
+            to_vec(&rga),
+            vec![1, 3],
+            "Deleted elements should not be visible."
+        );
+        assert_eq!(rga.len(), 2);
+        assert_eq!(
+            rga.insert_at(5, 4, nid, hlc.generate_timestamp()),
+            Err(RGAError::IndexOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn concurrent_inserts_converge() {
+        let mut hlc = SysTimeHLC::default();
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+
+        let mut rga_a = RGA::new();
+        let first = rga_a
+            .insert_at(0, 1u8, nid_a, hlc.generate_timestamp())
+            .unwrap();
+        let mut rga_b = RGA::new();
+        rga_b.apply(&first).unwrap();
+
+        let op_a = rga_a
+            .insert_at(1, 2, nid_a, hlc.generate_timestamp())
+            .unwrap();
+        let op_b = rga_b
+            .insert_at(1, 3, nid_b, hlc.generate_timestamp())
+            .unwrap();
+        let op_a2 = rga_a
+            .insert_at(2, 4, nid_a, hlc.generate_timestamp())
+            .unwrap();
+        let del_b = rga_b.delete_at(0).unwrap();
+
+        for op in [&op_b, &del_b] {
+            rga_a.apply(op).unwrap();
+        }
+        for op in [&op_a, &op_a2] {
+            rga_b.apply(op).unwrap();
+        }
+        assert_eq!(rga_a, rga_b, "Replicas should converge.");
+        assert_eq!(to_vec(&rga_a), vec![3, 2, 4]);
+    }
+
+    #[test]
+    fn merge_works() {
+        let mut hlc = SysTimeHLC::default();
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+
+        let mut rga_a = RGA::new();
+        rga_a
+            .insert_at(0, 1u8, nid_a, hlc.generate_timestamp())
+            .unwrap();
+        let mut rga_b = rga_a.clone();
+        rga_a
+            .insert_at(1, 2, nid_a, hlc.generate_timestamp())
+            .unwrap();
+        rga_b
+            .insert_at(0, 3, nid_b, hlc.generate_timestamp())
+            .unwrap();
+        rga_b.delete_at(1).unwrap();
+
+        let mut merged_a = rga_a.clone();
+        merged_a.merge(&rga_b).unwrap();
+        rga_b.merge(&rga_a).unwrap();
+        assert_eq!(merged_a, rga_b, "Merge should be commutative.");
+        assert_eq!(to_vec(&merged_a), vec![3, 2]);
+    }
+
+    #[test]
+    fn merge_rejects_unknown_origins() {
+        let mut hlc = SysTimeHLC::default();
+        let nid = UID::new();
+        let mut rga = RGA::new();
+        rga.insert_at(0, 1u8, nid, hlc.generate_timestamp())
+            .unwrap();
+
+        let orphan = RGA {
+            elements: vec![
+                RGAElement {
+                    id: Dot::new(nid, hlc.generate_timestamp()),
+                    origin: None,
+                    value: 2,
+                    deleted: false,
+                },
+                RGAElement {
+                    id: Dot::new(nid, hlc.generate_timestamp()),
+                    origin: Some(Dot::new(UID::new(), hlc.generate_timestamp())),
+                    value: 3,
+                    deleted: false,
+                },
+            ],
+        };
+        let before = rga.clone();
+        assert_eq!(rga.merge(&orphan), Err(RGAError::UnknownElement));
+        assert_eq!(
+            rga, before,
+            "Invalid states should not be partially merged."
+        );
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let mut hlc = SysTimeHLC::default();
+        let nid = UID::new();
+        let mut rga = RGA::new();
+        let op = rga
+            .insert_at(0, 1u8, nid, hlc.generate_timestamp())
+            .unwrap();
+        rga.insert_at(1, 2, nid, hlc.generate_timestamp()).unwrap();
+        let del = rga.delete_at(0).unwrap();
+
+        test_var_serialization(op);
+        test_var_serialization(del);
+        test_var_serialization(rga);
+    }
+}
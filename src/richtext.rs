@@ -0,0 +1,932 @@
+//! # Rich text CRDT
+//!
+//! Collaborative rich text: a [`Text`] together with formatting marks (bold, italic, links).
+//!
+//! Following Peritext, a mark is not stored as a range of indices but anchored to the IDs of the
+//! characters at its boundaries, so it keeps covering the same characters while the text is
+//! edited concurrently:
+//!
+//! * A mark starts right _before_ its first character, so text typed in front of a mark is not
+//!   formatted.
+//! * **Bold** and **italic** end right _before_ the character following the range, so text typed
+//!   at the end of the range extends the formatting (as in most editors).
+//! * **Links** end right _after_ their last character, so text typed at the end of a link is not
+//!   part of the link.
+//!
+//! Marks are ordered by their IDs. When several marks of the same type cover a character, the
+//! one with the largest ID decides whether (and with which value) the mark is applied.
+//!
+//! ### Changes
+//!
+//! Applying a remote operation returns the resulting changes as a _delta_ (as used by editors such
+//! as Quill): a list of retains, insertions and deletions with their formatting attributes, in
+//! UTF-16 code units.
+//!
+//! Reference: [Peritext: A CRDT for Collaborative Rich Text Editing - Litt et. al.](https://doi.org/10.1145/3555644)
+//!
+//! ### Usage
+//!
+//! ```rust
+//! use crdts::richtext::{DeltaOp, MarkType, RichText};
+//! use crdts::time::hlc::{HybridLogicalClock, SysTimeHLC};
+//! use crdts::uid::UID;
+//!
+//! let mut hlc = SysTimeHLC::default();
+//! let nid = UID::new();
+//!
+//! let mut text_a = RichText::new();
+//! let mut text_b = RichText::new();
+//! let insert = text_a.insert(0, "hello world", nid, hlc.generate_timestamp()).unwrap();
+//! let bold = text_a.format(0, 5, MarkType::Bold, Some(String::new()), nid, hlc.generate_timestamp()).unwrap();
+//! text_b.apply(&insert).unwrap();
+//!
+//! let delta = text_b.apply(&bold).unwrap();
+//! assert_eq!(delta, vec![DeltaOp::Retain {
+//!     len: 5,
+//!     attributes: [(MarkType::Bold, Some(String::new()))].into(),
+//! }]);
+//! ```
+use crate::{
+    dot::Dot,
+    serialization::{DeserializeError, VarDeserialize, VarSerialize},
+    text::{Text, TextError, TextOp},
+    time::timestamp::Timestamp,
+    uid::UID,
+};
+use std::cmp::max;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use wasm_bindgen::prelude::*;
+
+//#region Marks
+/// ## Mark type
+///
+/// Kind of formatting applied by a mark.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum MarkType {
+    Bold,
+    Italic,
+    Link,
+}
+
+impl MarkType {
+    /// ### Expands
+    ///
+    /// Returns whether text inserted at the end of a mark of this type gets the mark as well.
+    #[inline]
+    pub fn expands(&self) -> bool {
+        !matches!(self, MarkType::Link)
+    }
+
+    /// ### Name
+    ///
+    /// Returns the name of the mark type, as used for delta attributes.
+    pub fn name(&self) -> &'static str {
+        match self {
+            MarkType::Bold => "bold",
+            MarkType::Italic => "italic",
+            MarkType::Link => "link",
+        }
+    }
+
+    /// ### From name
+    ///
+    /// Returns the mark type with a given name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bold" => Some(MarkType::Bold),
+            "italic" => Some(MarkType::Italic),
+            "link" => Some(MarkType::Link),
+            _ => None,
+        }
+    }
+}
+
+impl VarSerialize for MarkType {
+    fn serialize_var(&self) -> Vec<u8> {
+        vec![*self as u8]
+    }
+}
+
+impl VarDeserialize for MarkType {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (kind, rest) = u8::deserialize_var(encoded)?;
+        match kind {
+            0 => Ok((MarkType::Bold, rest)),
+            1 => Ok((MarkType::Italic, rest)),
+            2 => Ok((MarkType::Link, rest)),
+            _ => Err(DeserializeError::InvalidValue),
+        }
+    }
+}
+
+/// ## Anchor
+///
+/// Boundary of a mark, given relative to the ID of a character (which may have been deleted).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Anchor {
+    Before(Dot),
+    After(Dot),
+    End,
+}
+
+impl VarSerialize for Anchor {
+    fn serialize_var(&self) -> Vec<u8> {
+        match self {
+            Anchor::Before(id) => [vec![0u8], id.serialize_var()].concat(),
+            Anchor::After(id) => [vec![1u8], id.serialize_var()].concat(),
+            Anchor::End => vec![2u8],
+        }
+    }
+}
+
+impl VarDeserialize for Anchor {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (kind, rest) = u8::deserialize_var(encoded)?;
+        match kind {
+            0 => Dot::deserialize_var(rest).map(|(id, rest)| (Anchor::Before(id), rest)),
+            1 => Dot::deserialize_var(rest).map(|(id, rest)| (Anchor::After(id), rest)),
+            2 => Ok((Anchor::End, rest)),
+            _ => Err(DeserializeError::InvalidValue),
+        }
+    }
+}
+
+/// ## Mark operation
+///
+/// Adds or removes a mark over a range of characters.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MarkOp {
+    /// ### ID
+    ///
+    /// Unique ID of the operation, which also gives its precedence over other marks.
+    pub id: Dot,
+
+    /// ### Start
+    ///
+    /// Start of the range.
+    pub start: Anchor,
+
+    /// ### End
+    ///
+    /// End of the range.
+    pub end: Anchor,
+
+    /// ### Mark type
+    pub mark_type: MarkType,
+
+    /// ### Value
+    ///
+    /// Value of the mark (the URL of a link, empty for bold and italic), or `None` to remove the
+    /// mark from the range.
+    pub value: Option<String>,
+}
+
+impl VarSerialize for MarkOp {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = self.id.serialize_var();
+        encoded.extend(self.start.serialize_var());
+        encoded.extend(self.end.serialize_var());
+        encoded.extend(self.mark_type.serialize_var());
+        encoded.extend(self.value.serialize_var());
+        encoded
+    }
+}
+
+impl VarDeserialize for MarkOp {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (id, rest) = Dot::deserialize_var(encoded)?;
+        let (start, rest) = Anchor::deserialize_var(rest)?;
+        let (end, rest) = Anchor::deserialize_var(rest)?;
+        let (mark_type, rest) = MarkType::deserialize_var(rest)?;
+        let (value, rest) = Option::deserialize_var(rest)?;
+        Ok((
+            Self {
+                id,
+                start,
+                end,
+                mark_type,
+                value,
+            },
+            rest,
+        ))
+    }
+}
+//#endregion
+
+//#region Operations
+/// ## Rich text operation
+///
+/// Operation generated by a local edit that can be sent to other replicas.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RichTextOp {
+    /// ### Text
+    ///
+    /// Inserts or deletes characters.
+    Text(TextOp),
+
+    /// ### Mark
+    ///
+    /// Adds or removes formatting.
+    Mark(MarkOp),
+}
+
+impl VarSerialize for RichTextOp {
+    fn serialize_var(&self) -> Vec<u8> {
+        match self {
+            RichTextOp::Text(op) => [vec![0u8], op.serialize_var()].concat(),
+            RichTextOp::Mark(op) => [vec![1u8], op.serialize_var()].concat(),
+        }
+    }
+}
+
+impl VarDeserialize for RichTextOp {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (kind, rest) = u8::deserialize_var(encoded)?;
+        match kind {
+            0 => TextOp::deserialize_var(rest).map(|(op, rest)| (RichTextOp::Text(op), rest)),
+            1 => MarkOp::deserialize_var(rest).map(|(op, rest)| (RichTextOp::Mark(op), rest)),
+            _ => Err(DeserializeError::InvalidValue),
+        }
+    }
+}
+//#endregion
+
+//#region Delta
+/// ## Attributes
+///
+/// Formatting attributes of a delta operation. A `None` value removes the mark.
+pub type Attributes = BTreeMap<MarkType, Option<String>>;
+
+/// ## Delta operation
+///
+/// Step of a delta describing a change to the text. Lengths are given in UTF-16 code units.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DeltaOp {
+    /// ### Retain
+    ///
+    /// Keeps `len` code units, changing their formatting by `attributes`.
+    Retain { len: usize, attributes: Attributes },
+
+    /// ### Insert
+    ///
+    /// Inserts `text` with the formatting given by `attributes`.
+    Insert {
+        text: String,
+        attributes: Attributes,
+    },
+
+    /// ### Delete
+    ///
+    /// Deletes `len` code units.
+    Delete { len: usize },
+}
+
+impl DeltaOp {
+    /// ### To JS object
+    ///
+    /// Converts the operation to a plain JS object of the form `{ retain, attributes? }`,
+    /// `{ insert, attributes? }` or `{ delete }`. Bold and italic attributes are given as
+    /// booleans, removed attributes as `null`.
+    fn to_js(&self) -> JsValue {
+        let obj = js_sys::Object::new();
+        let (key, value, attributes): (&str, JsValue, Option<&Attributes>) = match self {
+            DeltaOp::Retain { len, attributes } => {
+                ("retain", (*len as u32).into(), Some(attributes))
+            }
+            DeltaOp::Insert { text, attributes } => ("insert", text.into(), Some(attributes)),
+            DeltaOp::Delete { len } => ("delete", (*len as u32).into(), None),
+        };
+        js_sys::Reflect::set(&obj, &key.into(), &value).unwrap_throw();
+        if let Some(attributes) = attributes.filter(|attributes| !attributes.is_empty()) {
+            let attrs = js_sys::Object::new();
+            for (mark_type, value) in attributes {
+                let value = match (mark_type, value) {
+                    (_, None) => JsValue::NULL,
+                    (MarkType::Link, Some(url)) => url.into(),
+                    (_, Some(_)) => JsValue::TRUE,
+                };
+                js_sys::Reflect::set(&attrs, &mark_type.name().into(), &value).unwrap_throw();
+            }
+            js_sys::Reflect::set(&obj, &"attributes".into(), &attrs).unwrap_throw();
+        }
+        obj.into()
+    }
+
+    /// ### Push
+    ///
+    /// Appends an operation to a delta, merging it with the last one when possible.
+    fn push(delta: &mut Vec<DeltaOp>, op: DeltaOp) {
+        match (delta.last_mut(), op) {
+            (
+                Some(DeltaOp::Retain { len, attributes }),
+                DeltaOp::Retain {
+                    len: other_len,
+                    attributes: other_attributes,
+                },
+            ) if *attributes == other_attributes => *len += other_len,
+            (
+                Some(DeltaOp::Insert { text, attributes }),
+                DeltaOp::Insert {
+                    text: other_text,
+                    attributes: other_attributes,
+                },
+            ) if *attributes == other_attributes => text.push_str(&other_text),
+            (Some(DeltaOp::Delete { len }), DeltaOp::Delete { len: other_len }) => {
+                *len += other_len
+            }
+            (_, op) => delta.push(op),
+        }
+    }
+}
+//#endregion
+
+/// ## Rich text
+///
+/// Collaborative text with formatting marks.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RichText {
+    /// ### Text
+    ///
+    /// Characters of the text.
+    text: Text,
+
+    /// ### Marks
+    ///
+    /// Mark operations, sorted by ascending ID.
+    marks: Vec<MarkOp>,
+}
+
+impl RichText {
+    /// ### New rich text
+    ///
+    /// Creates a new empty text.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ### Length
+    ///
+    /// Returns the length of the text in UTF-16 code units.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.text.len()
+    }
+
+    /// ### Is empty
+    ///
+    /// Returns whether the text is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// ### Insert
+    ///
+    /// Inserts a string at a given position (in UTF-16 code units). See [`Text::insert`].
+    pub fn insert(
+        &mut self,
+        index: usize,
+        text: &str,
+        nid: UID,
+        ts: Timestamp,
+    ) -> Result<RichTextOp, RichTextError> {
+        self.text
+            .insert(index, text, nid, ts)
+            .map(RichTextOp::Text)
+            .map_err(RichTextError::from)
+    }
+
+    /// ### Delete
+    ///
+    /// Deletes a range of the text (in UTF-16 code units). See [`Text::delete`].
+    pub fn delete(&mut self, index: usize, len: usize) -> Result<RichTextOp, RichTextError> {
+        self.text
+            .delete(index, len)
+            .map(RichTextOp::Text)
+            .map_err(RichTextError::from)
+    }
+
+    /// ### Format
+    ///
+    /// Adds or removes a mark over a range of the text.
+    ///
+    /// * `index` - Start of the range in UTF-16 code units.
+    /// * `len` - Length of the range in UTF-16 code units.
+    /// * `mark_type` - Type of the mark.
+    /// * `value` - Value of the mark (the URL of a link, empty for bold and italic), or `None` to
+    ///   remove the mark.
+    /// * `nid` - ID of the current node.
+    /// * `ts` - Timestamp of the operation.
+    /// * Returns the operation to be sent to other replicas.
+    /// * Throws [`TextError::InvalidIndex`] (wrapped in [`RichTextError::Text`]) if the range is
+    ///   empty, out of bounds or splits a character.
+    #[allow(clippy::too_many_arguments)]
+    pub fn format(
+        &mut self,
+        index: usize,
+        len: usize,
+        mark_type: MarkType,
+        value: Option<String>,
+        nid: UID,
+        ts: Timestamp,
+    ) -> Result<RichTextOp, RichTextError> {
+        let ids = self.text.ids_in(index, len)?;
+        let (first, last) = match (ids.first(), ids.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return Err(TextError::InvalidIndex.into()),
+        };
+        let end = if mark_type.expands() {
+            // Anchor before the element following the range, even if it has been deleted.
+            self.text
+                .chars()
+                .iter_ids()
+                .skip_while(|(id, _)| *id != last)
+                .nth(1)
+                .map_or(Anchor::End, |(id, _)| Anchor::Before(id))
+        } else {
+            Anchor::After(last)
+        };
+        let mut next_ts = self.marks.last().map(|mark| mark.id.ts).unwrap_or_default();
+        next_ts.increase_counter();
+        let op = RichTextOp::Mark(MarkOp {
+            id: Dot::new(nid, max(ts, next_ts)),
+            start: Anchor::Before(first),
+            end,
+            mark_type,
+            value,
+        });
+        self.apply(&op)?;
+        Ok(op)
+    }
+
+    /// ### Apply
+    ///
+    /// Applies an operation (usually received from another replica).
+    ///
+    /// * Returns the resulting changes as a delta.
+    /// * Throws [`TextError::UnknownElement`] (wrapped in [`RichTextError::Text`]) if the
+    ///   operation references an unknown character.
+    pub fn apply(&mut self, op: &RichTextOp) -> Result<Vec<DeltaOp>, RichTextError> {
+        let before = self.formatted_chars();
+        match op {
+            RichTextOp::Text(op) => {
+                self.text.apply(op)?;
+            }
+            RichTextOp::Mark(op) => {
+                let chars = self.text.chars();
+                for anchor in [op.start, op.end] {
+                    if let Anchor::Before(id) | Anchor::After(id) = anchor {
+                        if !chars.contains(&id) {
+                            return Err(TextError::UnknownElement.into());
+                        }
+                    }
+                }
+                let position = self.marks.partition_point(|mark| mark.id < op.id);
+                if self.marks.get(position).map(|mark| mark.id) != Some(op.id) {
+                    self.marks.insert(position, op.clone());
+                }
+            }
+        }
+        Ok(self.delta_from(&before))
+    }
+
+    /// ### Merge
+    ///
+    /// Merges the state of another text into the current one.
+    ///
+    /// * Throws [`TextError::UnknownElement`] (wrapped in [`RichTextError::Text`]) if the other
+    ///   state references an unknown character, in which case the text is left unchanged.
+    pub fn merge(&mut self, other: &Self) -> Result<(), RichTextError> {
+        self.text.merge(&other.text)?;
+        for mark in &other.marks {
+            let position = self.marks.partition_point(|current| current.id < mark.id);
+            if self.marks.get(position).map(|current| current.id) != Some(mark.id) {
+                self.marks.insert(position, mark.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// ### To delta
+    ///
+    /// Returns the whole text as a delta of insertions.
+    pub fn to_delta(&self) -> Vec<DeltaOp> {
+        self.delta_from(&BTreeMap::new())
+    }
+
+    /// ### Formatted characters
+    ///
+    /// Returns every visible character with its formatting, by character ID.
+    fn formatted_chars(&self) -> BTreeMap<Dot, (char, Attributes)> {
+        let chars = self.text.chars();
+        let points: BTreeMap<Dot, usize> = chars
+            .iter_ids()
+            .enumerate()
+            .map(|(position, (id, _))| (id, position))
+            .collect();
+        // Characters are placed at odd points, anchors at the even points around them.
+        let point = |anchor: &Anchor| match anchor {
+            Anchor::Before(id) => points.get(id).map_or(usize::MAX, |p| 2 * p),
+            Anchor::After(id) => points.get(id).map_or(usize::MAX, |p| 2 * p + 2),
+            Anchor::End => usize::MAX,
+        };
+
+        chars
+            .iter_ids()
+            .filter_map(|(id, c)| c.map(|c| (id, *c)))
+            .map(|(id, c)| {
+                let char_point = 2 * points[&id] + 1;
+                let mut attributes = Attributes::new();
+                for mark in &self.marks {
+                    if point(&mark.start) < char_point && char_point < point(&mark.end) {
+                        match &mark.value {
+                            Some(value) => attributes.insert(mark.mark_type, Some(value.clone())),
+                            None => attributes.remove(&mark.mark_type),
+                        };
+                    }
+                }
+                (id, (c, attributes))
+            })
+            .collect()
+    }
+
+    /// ### Delta from
+    ///
+    /// Computes the delta that turns a previous state (given by its formatted characters) into
+    /// the current one.
+    fn delta_from(&self, before: &BTreeMap<Dot, (char, Attributes)>) -> Vec<DeltaOp> {
+        let after = self.formatted_chars();
+        let mut delta = Vec::new();
+        for (id, _) in self.text.chars().iter_ids() {
+            let op = match (before.get(&id), after.get(&id)) {
+                (Some((c, old)), Some((_, new))) => {
+                    let mut attributes: Attributes = new
+                        .iter()
+                        .filter(|(mark_type, value)| old.get(mark_type) != Some(value))
+                        .map(|(mark_type, value)| (*mark_type, value.clone()))
+                        .collect();
+                    for mark_type in old.keys().filter(|mark_type| !new.contains_key(mark_type)) {
+                        attributes.insert(*mark_type, None);
+                    }
+                    DeltaOp::Retain {
+                        len: c.len_utf16(),
+                        attributes,
+                    }
+                }
+                (None, Some((c, new))) => DeltaOp::Insert {
+                    text: String::from(*c),
+                    attributes: new.clone(),
+                },
+                (Some((c, _)), None) => DeltaOp::Delete { len: c.len_utf16() },
+                (None, None) => continue,
+            };
+            DeltaOp::push(&mut delta, op);
+        }
+        if let Some(DeltaOp::Retain { attributes, .. }) = delta.last() {
+            if attributes.is_empty() {
+                delta.pop();
+            }
+        }
+        delta
+    }
+}
+
+#[wasm_bindgen]
+impl RichText {
+    /// ### New rich text (JS)
+    ///
+    /// Creates a new empty text.
+    #[wasm_bindgen(constructor)]
+    pub fn new_js() -> RichText {
+        Self::default()
+    }
+
+    /// ### Insert (JS)
+    ///
+    /// Inserts a string at a given position (in UTF-16 code units) and returns the encoded
+    /// operation.
+    ///
+    /// #### Errors
+    ///
+    /// A JS exception is thrown if the position is not valid.
+    #[wasm_bindgen(js_name = insert)]
+    pub fn insert_js(&mut self, index: usize, text: &str, nid: UID, ts: Timestamp) -> Vec<u8> {
+        self.insert(index, text, nid, ts)
+            .unwrap_throw()
+            .serialize_var()
+    }
+
+    /// ### Delete (JS)
+    ///
+    /// Deletes a range (in UTF-16 code units) and returns the encoded operation.
+    ///
+    /// #### Errors
+    ///
+    /// A JS exception is thrown if the range is not valid.
+    #[wasm_bindgen(js_name = delete)]
+    pub fn delete_js(&mut self, index: usize, len: usize) -> Vec<u8> {
+        self.delete(index, len).unwrap_throw().serialize_var()
+    }
+
+    /// ### Format (JS)
+    ///
+    /// Adds (or removes, if `value` is `undefined`) a mark named `"bold"`, `"italic"` or `"link"`
+    /// over a range and returns the encoded operation.
+    ///
+    /// #### Errors
+    ///
+    /// A JS exception is thrown if the mark or the range are not valid.
+    #[wasm_bindgen(js_name = format)]
+    pub fn format_js(
+        &mut self,
+        index: usize,
+        len: usize,
+        mark: &str,
+        value: Option<String>,
+        nid: UID,
+        ts: Timestamp,
+    ) -> Vec<u8> {
+        let mark_type = MarkType::from_name(mark)
+            .ok_or(RichTextError::UnknownMark)
+            .unwrap_throw();
+        self.format(index, len, mark_type, value, nid, ts)
+            .unwrap_throw()
+            .serialize_var()
+    }
+
+    /// ### Apply operation (JS)
+    ///
+    /// Applies an encoded operation and returns the resulting delta as an array of objects
+    /// `{ retain, attributes? }`, `{ insert, attributes? }` or `{ delete }`.
+    ///
+    /// #### Errors
+    ///
+    /// A JS exception is thrown if the operation cannot be decoded or applied.
+    #[wasm_bindgen(js_name = applyOp)]
+    pub fn apply_js(&mut self, encoded: &[u8]) -> js_sys::Array {
+        let (op, _) = RichTextOp::deserialize_var(encoded).unwrap_throw();
+        self.apply(&op)
+            .unwrap_throw()
+            .iter()
+            .map(DeltaOp::to_js)
+            .collect()
+    }
+
+    /// ### To delta (JS)
+    ///
+    /// Returns the whole text as a delta of insertions, to initialize an editor.
+    #[wasm_bindgen(js_name = toDelta)]
+    pub fn to_delta_js(&self) -> js_sys::Array {
+        self.to_delta().iter().map(DeltaOp::to_js).collect()
+    }
+
+    /// ### To string
+    ///
+    /// Returns the current content of the text, without formatting.
+    #[wasm_bindgen(js_name = toString)]
+    pub fn as_string(&self) -> String {
+        self.to_string()
+    }
+
+    /// ### Serialize
+    ///
+    /// Returns the state of the text in binary format.
+    #[wasm_bindgen(js_name = serialize)]
+    pub fn serialize_js(&self) -> Vec<u8> {
+        self.serialize_var()
+    }
+
+    /// ### Deserialize
+    ///
+    /// Constructs a text from an encoded version.
+    ///
+    /// #### Errors
+    ///
+    /// A JS exception is thrown if the encoded state is not valid.
+    #[wasm_bindgen(js_name = deserialize)]
+    pub fn deserialize_js(encoded: &[u8]) -> RichText {
+        RichText::deserialize_var(encoded).unwrap_throw().0
+    }
+}
+
+impl Display for RichText {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+//#region Serialization
+impl VarSerialize for RichText {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = self.text.serialize_var();
+        encoded.extend(self.marks.serialize_var());
+        encoded
+    }
+}
+
+impl VarDeserialize for RichText {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (text, rest) = Text::deserialize_var(encoded)?;
+        let (marks, rest) = Vec::deserialize_var(rest)?;
+        Ok((Self { text, marks }, rest))
+    }
+}
+//#endregion
+
+//#region RichTextError
+/// ## Rich text error
+///
+/// Custom error related to rich text edits: an error of the underlying text, or a mark name
+/// that is not known.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RichTextError {
+    Text(TextError),
+    UnknownMark,
+}
+
+impl From<TextError> for RichTextError {
+    fn from(err: TextError) -> Self {
+        RichTextError::Text(err)
+    }
+}
+//#endregion
+
+#[cfg(test)]
+mod richtext_tests {
+    use super::*;
+    use crate::serialization::test_var_serialization;
+    use crate::time::hlc::{HybridLogicalClock, SysTimeHLC};
+
+    fn bold() -> Attributes {
+        [(MarkType::Bold, Some(String::new()))].into()
+    }
+
+    #[test]
+    fn marks_expand_according_to_type() {
+        let mut hlc = SysTimeHLC::default();
+        let nid = UID::new();
+        let mut text = RichText::new();
+        let mut replica = RichText::new();
+        let link = Some(String::from("https://example.com"));
+
+        let op = text
+            .insert(0, "abcd", nid, hlc.generate_timestamp())
+            .unwrap();
+        replica.apply(&op).unwrap();
+        let op = text
+            .format(
+                0,
+                2,
+                MarkType::Bold,
+                Some(String::new()),
+                nid,
+                hlc.generate_timestamp(),
+            )
+            .unwrap();
+        replica.apply(&op).unwrap();
+        let op = text
+            .format(
+                2,
+                2,
+                MarkType::Link,
+                link.clone(),
+                nid,
+                hlc.generate_timestamp(),
+            )
+            .unwrap();
+        replica.apply(&op).unwrap();
+
+        // Text typed at the end of the bold range is bold, text after the link is not a link.
+        let op = text.insert(2, "x", nid, hlc.generate_timestamp()).unwrap();
+        assert_eq!(
+            replica.apply(&op).unwrap(),
+            vec![
+                DeltaOp::Retain {
+                    len: 2,
+                    attributes: Attributes::new()
+                },
+                DeltaOp::Insert {
+                    text: String::from("x"),
+                    attributes: bold()
+                }
+            ]
+        );
+        let op = text.insert(5, "y", nid, hlc.generate_timestamp()).unwrap();
+        assert_eq!(
+            replica.apply(&op).unwrap(),
+            vec![
+                DeltaOp::Retain {
+                    len: 5,
+                    attributes: Attributes::new()
+                },
+                DeltaOp::Insert {
+                    text: String::from("y"),
+                    attributes: Attributes::new()
+                }
+            ]
+        );
+
+        assert_eq!(
+            replica.to_delta(),
+            vec![
+                DeltaOp::Insert {
+                    text: String::from("abx"),
+                    attributes: bold()
+                },
+                DeltaOp::Insert {
+                    text: String::from("cd"),
+                    attributes: [(MarkType::Link, link)].into()
+                },
+                DeltaOp::Insert {
+                    text: String::from("y"),
+                    attributes: Attributes::new()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn concurrent_formatting_converges() {
+        let mut hlc = SysTimeHLC::default();
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut text_a = RichText::new();
+        let op = text_a
+            .insert(0, "hello", nid_a, hlc.generate_timestamp())
+            .unwrap();
+        let mut text_b = RichText::new();
+        text_b.apply(&op).unwrap();
+
+        let op_a = text_a
+            .format(
+                0,
+                5,
+                MarkType::Bold,
+                Some(String::new()),
+                nid_a,
+                hlc.generate_timestamp(),
+            )
+            .unwrap();
+        let op_b = text_b
+            .format(2, 3, MarkType::Bold, None, nid_b, hlc.generate_timestamp())
+            .unwrap();
+        let delete = text_b.delete(4, 1).unwrap();
+
+        assert_eq!(
+            text_a.apply(&op_b).unwrap(),
+            vec![
+                DeltaOp::Retain {
+                    len: 2,
+                    attributes: Attributes::new()
+                },
+                DeltaOp::Retain {
+                    len: 3,
+                    attributes: [(MarkType::Bold, None)].into()
+                }
+            ]
+        );
+        assert_eq!(
+            text_a.apply(&delete).unwrap(),
+            vec![
+                DeltaOp::Retain {
+                    len: 4,
+                    attributes: Attributes::new()
+                },
+                DeltaOp::Delete { len: 1 }
+            ]
+        );
+        text_b.apply(&op_a).unwrap();
+        assert_eq!(
+            text_a.to_delta(),
+            text_b.to_delta(),
+            "Replicas should converge."
+        );
+        assert_eq!(text_a.to_string(), "hell");
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let mut hlc = SysTimeHLC::default();
+        let nid = UID::new();
+        let mut text = RichText::new();
+        let insert = text
+            .insert(0, "abc", nid, hlc.generate_timestamp())
+            .unwrap();
+        let mark = text
+            .format(
+                1,
+                2,
+                MarkType::Link,
+                Some(String::from("url")),
+                nid,
+                hlc.generate_timestamp(),
+            )
+            .unwrap();
+
+        test_var_serialization(insert);
+        test_var_serialization(mark);
+        test_var_serialization(text);
+    }
+}
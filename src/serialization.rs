@@ -1,7 +1,11 @@
+use std::collections::{BTreeMap, BTreeSet};
+
 //#region Type sizes
 pub const UID_SIZE: usize = 16;
 pub const TS_SIZE: usize = 8;
 pub const BOOL_SIZE: usize = 1;
+/// Size of the length prefix used by variable-size encodings.
+pub const LEN_SIZE: usize = 4;
 //#endregion
 
 pub trait Serialize<const NUM_BYTES: usize> {
@@ -24,6 +28,297 @@ impl Deserialize<BOOL_SIZE> for bool {
     }
 }
 
+//#region Variable-size serialization
+/// ## Deserialize error
+///
+/// Error raised when a variable-size encoding cannot be decoded.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DeserializeError {
+    /// The buffer ended before the value was completely decoded.
+    UnexpectedEnd,
+    /// The buffer contains bytes that do not correspond to a valid value.
+    InvalidValue,
+}
+
+/// ## Serialize (variable size)
+///
+/// Binary encoding for types whose encoded size depends on their content, such as collections.
+///
+/// Encodings are self-delimiting, so that several values can be concatenated in a single buffer.
+pub trait VarSerialize {
+    fn serialize_var(&self) -> Vec<u8>;
+}
+
+/// ## Deserialize (variable size)
+///
+/// Decodes a value from the beginning of a buffer.
+///
+/// * Returns the decoded value and the rest of the buffer.
+pub trait VarDeserialize: VarSerialize + Sized {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError>;
+}
+
+/// ## Split fixed
+///
+/// Splits off the first `N` bytes of a buffer.
+///
+/// * Returns the leading bytes and the rest of the buffer.
+/// * Throws [`DeserializeError::UnexpectedEnd`] if the buffer is too short.
+pub fn split_fixed<const N: usize>(encoded: &[u8]) -> Result<([u8; N], &[u8]), DeserializeError> {
+    if encoded.len() < N {
+        return Err(DeserializeError::UnexpectedEnd);
+    }
+    let (head, rest) = encoded.split_at(N);
+    Ok((head.try_into().unwrap(), rest))
+}
+
+/// ## Encode length
+///
+/// Encodes a collection length as a 4-byte prefix.
+pub fn encode_len(len: usize) -> [u8; LEN_SIZE] {
+    (len as u32).to_be_bytes()
+}
+
+/// ## Decode length
+///
+/// Decodes a 4-byte length prefix from the beginning of a buffer.
+pub fn decode_len(encoded: &[u8]) -> Result<(usize, &[u8]), DeserializeError> {
+    let (len, rest) = split_fixed::<LEN_SIZE>(encoded)?;
+    Ok((u32::from_be_bytes(len) as usize, rest))
+}
+
+/// ## Encode varint
+///
+/// Encodes an integer in as few bytes as needed (LEB128): 7 bits per byte, least significant
+/// group first, with the high bit set on every byte but the last.
+pub fn encode_varint(mut value: u64) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    while value >= 0x80 {
+        encoded.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    encoded.push(value as u8);
+    encoded
+}
+
+/// ## Decode varint
+///
+/// Decodes an integer encoded with [`encode_varint`] from the beginning of a buffer.
+///
+/// * Returns the decoded integer and the rest of the buffer.
+/// * Throws [`DeserializeError::UnexpectedEnd`] if the buffer ends in the middle of the integer.
+/// * Throws [`DeserializeError::InvalidValue`] if the integer does not fit in a `u64`.
+pub fn decode_varint(encoded: &[u8]) -> Result<(u64, &[u8]), DeserializeError> {
+    let mut value = 0u64;
+    for (index, byte) in encoded.iter().enumerate() {
+        let shift = 7 * index as u32;
+        if shift >= u64::BITS || (shift == 63 && *byte > 1) {
+            return Err(DeserializeError::InvalidValue);
+        }
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok((value, &encoded[index + 1..]));
+        }
+    }
+    Err(DeserializeError::UnexpectedEnd)
+}
+
+/// ## Implement variable-size serialization
+///
+/// Implements [`VarSerialize`] and [`VarDeserialize`] for a type that has a fixed-size encoding.
+#[macro_export]
+macro_rules! impl_var_serialization {
+    ($t:ty, $size:expr) => {
+        impl $crate::serialization::VarSerialize for $t {
+            fn serialize_var(&self) -> Vec<u8> {
+                $crate::serialization::Serialize::<{ $size }>::serialize(self).to_vec()
+            }
+        }
+
+        impl $crate::serialization::VarDeserialize for $t {
+            fn deserialize_var(
+                encoded: &[u8],
+            ) -> Result<(Self, &[u8]), $crate::serialization::DeserializeError> {
+                let (head, rest) = $crate::serialization::split_fixed::<{ $size }>(encoded)?;
+                Ok((
+                    $crate::serialization::Deserialize::<{ $size }>::deserialize(head),
+                    rest,
+                ))
+            }
+        }
+    };
+}
+
+impl_var_serialization!(bool, BOOL_SIZE);
+
+/// ## Implement numeric variable-size serialization
+///
+/// Implements [`VarSerialize`] and [`VarDeserialize`] for numeric types using their big-endian
+/// byte representation.
+macro_rules! impl_num_var_serialization {
+    ($($t:ty),*) => {
+        $(
+            impl VarSerialize for $t {
+                fn serialize_var(&self) -> Vec<u8> {
+                    self.to_be_bytes().to_vec()
+                }
+            }
+
+            impl VarDeserialize for $t {
+                fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+                    let (bytes, rest) = split_fixed::<{ std::mem::size_of::<$t>() }>(encoded)?;
+                    Ok((<$t>::from_be_bytes(bytes), rest))
+                }
+            }
+        )*
+    };
+}
+
+impl_num_var_serialization!(u8, u32, u64, i64, f64);
+
+impl VarSerialize for char {
+    fn serialize_var(&self) -> Vec<u8> {
+        (*self as u32).serialize_var()
+    }
+}
+
+impl VarDeserialize for char {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (code, rest) = u32::deserialize_var(encoded)?;
+        let value = char::from_u32(code).ok_or(DeserializeError::InvalidValue)?;
+        Ok((value, rest))
+    }
+}
+
+impl VarSerialize for String {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = encode_len(self.len()).to_vec();
+        encoded.extend(self.as_bytes());
+        encoded
+    }
+}
+
+impl VarDeserialize for String {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (len, rest) = decode_len(encoded)?;
+        if rest.len() < len {
+            return Err(DeserializeError::UnexpectedEnd);
+        }
+        let (bytes, rest) = rest.split_at(len);
+        let value =
+            String::from_utf8(bytes.to_vec()).map_err(|_| DeserializeError::InvalidValue)?;
+        Ok((value, rest))
+    }
+}
+
+impl<T: VarSerialize> VarSerialize for Option<T> {
+    fn serialize_var(&self) -> Vec<u8> {
+        match self {
+            Some(value) => {
+                let mut encoded = vec![1u8];
+                encoded.extend(value.serialize_var());
+                encoded
+            }
+            None => vec![0u8],
+        }
+    }
+}
+
+impl<T: VarDeserialize> VarDeserialize for Option<T> {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (is_some, rest) = bool::deserialize_var(encoded)?;
+        if is_some {
+            let (value, rest) = T::deserialize_var(rest)?;
+            Ok((Some(value), rest))
+        } else {
+            Ok((None, rest))
+        }
+    }
+}
+
+impl<A: VarSerialize, B: VarSerialize> VarSerialize for (A, B) {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = self.0.serialize_var();
+        encoded.extend(self.1.serialize_var());
+        encoded
+    }
+}
+
+impl<A: VarDeserialize, B: VarDeserialize> VarDeserialize for (A, B) {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (a, rest) = A::deserialize_var(encoded)?;
+        let (b, rest) = B::deserialize_var(rest)?;
+        Ok(((a, b), rest))
+    }
+}
+
+impl<T: VarSerialize> VarSerialize for Vec<T> {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = encode_len(self.len()).to_vec();
+        for item in self {
+            encoded.extend(item.serialize_var());
+        }
+        encoded
+    }
+}
+
+impl<T: VarDeserialize> VarDeserialize for Vec<T> {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (len, mut rest) = decode_len(encoded)?;
+        let mut items = Vec::new();
+        for _ in 0..len {
+            let (item, remaining) = T::deserialize_var(rest)?;
+            items.push(item);
+            rest = remaining;
+        }
+        Ok((items, rest))
+    }
+}
+
+impl<T: VarSerialize> VarSerialize for BTreeSet<T> {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = encode_len(self.len()).to_vec();
+        for item in self {
+            encoded.extend(item.serialize_var());
+        }
+        encoded
+    }
+}
+
+impl<T: VarDeserialize + Ord> VarDeserialize for BTreeSet<T> {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (items, rest) = Vec::<T>::deserialize_var(encoded)?;
+        Ok((items.into_iter().collect(), rest))
+    }
+}
+
+impl<K: VarSerialize, V: VarSerialize> VarSerialize for BTreeMap<K, V> {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = encode_len(self.len()).to_vec();
+        for (key, value) in self {
+            encoded.extend(key.serialize_var());
+            encoded.extend(value.serialize_var());
+        }
+        encoded
+    }
+}
+
+impl<K: VarDeserialize + Ord, V: VarDeserialize> VarDeserialize for BTreeMap<K, V> {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (len, mut rest) = decode_len(encoded)?;
+        let mut map = BTreeMap::new();
+        for _ in 0..len {
+            let (key, remaining) = K::deserialize_var(rest)?;
+            let (value, remaining) = V::deserialize_var(remaining)?;
+            map.insert(key, value);
+            rest = remaining;
+        }
+        Ok((map, rest))
+    }
+}
+//#endregion
+
+//#region Generic tests
 pub fn test_serialization<T, const NUM_BYTES: usize>()
 where
     T: Serialize<NUM_BYTES> + Deserialize<NUM_BYTES> + Default + Eq + PartialEq + std::fmt::Debug,
@@ -37,6 +332,26 @@ where
     );
 }
 
+/// ## Test variable-size serialization
+///
+/// Checks that encoding and decoding a value gives back the same value and consumes the whole
+/// encoding.
+pub fn test_var_serialization<T>(obj: T)
+where
+    T: VarDeserialize + PartialEq + std::fmt::Debug,
+{
+    let encoded = obj.serialize_var();
+    let (decoded, rest) =
+        T::deserialize_var(&encoded).expect("Decoding an encoded value should work.");
+
+    assert_eq!(
+        obj, decoded,
+        "Serialization + deserialization shouldn't change the value."
+    );
+    assert!(rest.is_empty(), "The whole encoding should be consumed.");
+}
+//#endregion
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -45,4 +360,44 @@ mod tests {
     fn bool_serialization_deserialization_works() {
         test_serialization::<bool, BOOL_SIZE>();
     }
+
+    #[test]
+    fn collection_serialization_deserialization_works() {
+        test_var_serialization(vec![true, false, true]);
+        test_var_serialization(BTreeSet::from([1u8, 2, 3]));
+        test_var_serialization(BTreeMap::from([(1u8, vec![true]), (2u8, vec![])]));
+        test_var_serialization(String::from("K-CRDT"));
+        test_var_serialization(Some(1u32));
+        test_var_serialization(None::<u32>);
+        test_var_serialization((1u8, String::from("a")));
+    }
+
+    #[test]
+    fn truncated_input_fails() {
+        let encoded = vec![1u8, 2, 3].serialize_var();
+        assert_eq!(
+            Vec::<u8>::deserialize_var(&encoded[..encoded.len() - 1]),
+            Err(DeserializeError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn varint_encoding_works() {
+        for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+            let mut encoded = encode_varint(value);
+            encoded.push(42);
+            assert_eq!(decode_varint(&encoded), Ok((value, &[42u8][..])));
+        }
+        assert_eq!(encode_varint(127).len(), 1);
+        assert_eq!(encode_varint(128).len(), 2);
+        assert_eq!(encode_varint(u64::MAX).len(), 10);
+
+        assert_eq!(decode_varint(&[0x80]), Err(DeserializeError::UnexpectedEnd));
+        let mut too_long = vec![0xff; 9];
+        too_long.push(0x02);
+        assert_eq!(
+            decode_varint(&too_long),
+            Err(DeserializeError::InvalidValue)
+        );
+    }
 }
@@ -0,0 +1,638 @@
+//! # Text CRDT
+//!
+//! Collaborative plain text built on top of an [`RGA`] of characters.
+//!
+//! Positions are given in UTF-16 code units, since that is how JavaScript strings (and hence text
+//! editors running in the browser) index text. Every local edit returns a [`TextOp`] that can be
+//! encoded and applied on other replicas. Applying a remote operation returns the
+//! [`TextChange`]s that an editor needs to update its own buffer.
+//!
+//! **Compact insertions.** An insertion of several characters is sent as a single operation.
+//! The characters get consecutive timestamps starting from the timestamp of the operation, so
+//! that only the ID of the first one is included.
+//!
+//! ### Usage
+//!
+//! ```rust
+//! use crdts::text::{Text, TextChange};
+//! use crdts::time::hlc::{HybridLogicalClock, SysTimeHLC};
+//! use crdts::uid::UID;
+//!
+//! let mut hlc = SysTimeHLC::default();
+//! let (nid_a, nid_b) = (UID::new(), UID::new());
+//!
+//! let mut text_a = Text::new();
+//! let mut text_b = Text::new();
+//! let op = text_a.insert(0, "héllo 👋", nid_a, hlc.generate_timestamp()).unwrap();
+//! text_b.apply(&op).unwrap();
+//!
+//! let op = text_b.insert(8, "!", nid_b, hlc.generate_timestamp()).unwrap();
+//! let changes = text_a.apply(&op).unwrap();
+//!
+//! assert_eq!(text_a.to_string(), "héllo 👋!");
+//! assert_eq!(changes, vec![TextChange::Insert { index: 8, text: String::from("!") }]);
+//! ```
+use crate::{
+    dot::Dot,
+    rga::{RGAError, RGA},
+    serialization::{DeserializeError, VarDeserialize, VarSerialize},
+    time::timestamp::Timestamp,
+    uid::UID,
+};
+use std::cmp::max;
+use std::fmt::{Display, Formatter};
+use wasm_bindgen::prelude::*;
+
+//#region Operations
+/// ## Text operation
+///
+/// Operation generated by a local edit that can be sent to other replicas.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TextOp {
+    /// ### Insert
+    ///
+    /// Inserts `text` right after the character `origin` (or at the start). The `i`-th
+    /// character gets the ID of the first one with its timestamp increased `i` times.
+    Insert {
+        id: Dot,
+        origin: Option<Dot>,
+        text: String,
+    },
+
+    /// ### Delete
+    ///
+    /// Deletes the characters with the given IDs.
+    Delete { ids: Vec<Dot> },
+}
+
+impl VarSerialize for TextOp {
+    fn serialize_var(&self) -> Vec<u8> {
+        match self {
+            TextOp::Insert { id, origin, text } => {
+                let mut encoded = vec![0u8];
+                encoded.extend(id.serialize_var());
+                encoded.extend(origin.serialize_var());
+                encoded.extend(text.serialize_var());
+                encoded
+            }
+            TextOp::Delete { ids } => {
+                let mut encoded = vec![1u8];
+                encoded.extend(ids.serialize_var());
+                encoded
+            }
+        }
+    }
+}
+
+impl VarDeserialize for TextOp {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (kind, rest) = u8::deserialize_var(encoded)?;
+        match kind {
+            0 => {
+                let (id, rest) = Dot::deserialize_var(rest)?;
+                let (origin, rest) = Option::<Dot>::deserialize_var(rest)?;
+                let (text, rest) = String::deserialize_var(rest)?;
+                Ok((TextOp::Insert { id, origin, text }, rest))
+            }
+            1 => {
+                let (ids, rest) = Vec::<Dot>::deserialize_var(rest)?;
+                Ok((TextOp::Delete { ids }, rest))
+            }
+            _ => Err(DeserializeError::InvalidValue),
+        }
+    }
+}
+//#endregion
+
+//#region Changes
+/// ## Text change
+///
+/// Index-based change resulting from applying a remote operation. Indices and lengths are given
+/// in UTF-16 code units and refer to the text as it is right before the change.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TextChange {
+    Insert { index: usize, text: String },
+    Delete { index: usize, len: usize },
+}
+
+impl TextChange {
+    /// ### To JS object
+    ///
+    /// Converts the change to a plain JS object of the form `{ kind, index, text }` or
+    /// `{ kind, index, length }`.
+    fn to_js(&self) -> JsValue {
+        let obj = js_sys::Object::new();
+        let (kind, index) = match self {
+            TextChange::Insert { index, text } => {
+                js_sys::Reflect::set(&obj, &"text".into(), &text.into()).unwrap_throw();
+                ("insert", index)
+            }
+            TextChange::Delete { index, len } => {
+                js_sys::Reflect::set(&obj, &"length".into(), &(*len as u32).into()).unwrap_throw();
+                ("delete", index)
+            }
+        };
+        js_sys::Reflect::set(&obj, &"kind".into(), &kind.into()).unwrap_throw();
+        js_sys::Reflect::set(&obj, &"index".into(), &(*index as u32).into()).unwrap_throw();
+        obj.into()
+    }
+}
+//#endregion
+
+/// ## Text
+///
+/// Collaborative plain text.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Text {
+    /// ### Characters
+    ///
+    /// Sequence of characters of the text.
+    chars: RGA<char>,
+
+    /// ### Last timestamp
+    ///
+    /// Largest timestamp among the IDs of the characters. New characters always get a larger
+    /// timestamp, so that they are ordered after the characters they were inserted after.
+    last_ts: Timestamp,
+}
+
+impl Text {
+    /// ### New text
+    ///
+    /// Creates a new empty text.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ### Length
+    ///
+    /// Returns the length of the text in UTF-16 code units.
+    pub fn len(&self) -> usize {
+        self.chars.iter().map(|c| c.len_utf16()).sum()
+    }
+
+    /// ### Is empty
+    ///
+    /// Returns whether the text is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+
+    /// ### Insert
+    ///
+    /// Inserts a string at a given position.
+    ///
+    /// * `index` - Position in UTF-16 code units.
+    /// * `text` - String to insert.
+    /// * `nid` - ID of the current node.
+    /// * `ts` - Timestamp of the operation.
+    /// * Returns the operation to be sent to other replicas.
+    /// * Throws [`TextError::InvalidIndex`] if the position is out of bounds or splits a character.
+    pub fn insert(
+        &mut self,
+        index: usize,
+        text: &str,
+        nid: UID,
+        ts: Timestamp,
+    ) -> Result<TextOp, TextError> {
+        let char_index = self.char_index(index)?;
+        let origin = match char_index {
+            0 => None,
+            _ => self.chars.id_at(char_index - 1),
+        };
+        let mut next_ts = self.last_ts;
+        next_ts.increase_counter();
+        let op = TextOp::Insert {
+            id: Dot::new(nid, max(ts, next_ts)),
+            origin,
+            text: String::from(text),
+        };
+        self.apply(&op)?;
+        Ok(op)
+    }
+
+    /// ### Delete
+    ///
+    /// Deletes a range of the text.
+    ///
+    /// * `index` - Start of the range in UTF-16 code units.
+    /// * `len` - Length of the range in UTF-16 code units.
+    /// * Returns the operation to be sent to other replicas.
+    /// * Throws [`TextError::InvalidIndex`] if the range is out of bounds or splits a character.
+    pub fn delete(&mut self, index: usize, len: usize) -> Result<TextOp, TextError> {
+        let op = TextOp::Delete {
+            ids: self.ids_in(index, len)?,
+        };
+        self.apply(&op)?;
+        Ok(op)
+    }
+
+    /// ### IDs in range
+    ///
+    /// Returns the IDs of the characters in a range of the text.
+    ///
+    /// * `index` - Start of the range in UTF-16 code units.
+    /// * `len` - Length of the range in UTF-16 code units.
+    /// * Throws [`TextError::InvalidIndex`] if the range is out of bounds or splits a character.
+    pub fn ids_in(&self, index: usize, len: usize) -> Result<Vec<Dot>, TextError> {
+        let start = self.char_index(index)?;
+        let end = self.char_index(index + len)?;
+        (start..end)
+            .map(|i| self.chars.id_at(i).ok_or(TextError::InvalidIndex))
+            .collect()
+    }
+
+    /// ### Characters
+    ///
+    /// Returns the underlying sequence of characters, including deleted ones.
+    #[inline]
+    pub fn chars(&self) -> &RGA<char> {
+        &self.chars
+    }
+
+    /// ### Apply
+    ///
+    /// Applies an operation (usually received from another replica).
+    ///
+    /// * Returns the resulting changes in UTF-16 code units, in the order in which they have to
+    ///   be applied.
+    /// * Throws [`TextError::UnknownElement`] if the operation references an unknown character,
+    ///   in which case the text is left unchanged.
+    pub fn apply(&mut self, op: &TextOp) -> Result<Vec<TextChange>, TextError> {
+        let is_known = match op {
+            TextOp::Insert { origin, .. } => {
+                origin.is_none_or(|origin| self.chars.contains(&origin))
+            }
+            TextOp::Delete { ids } => ids.iter().all(|id| self.chars.contains(id)),
+        };
+        if !is_known {
+            return Err(TextError::UnknownElement);
+        }
+
+        let mut changes: Vec<TextChange> = Vec::new();
+        match op {
+            TextOp::Insert { id, origin, text } => {
+                let mut origin = *origin;
+                let mut ts = id.ts;
+                for c in text.chars() {
+                    let char_id = Dot::new(id.nid, ts);
+                    if !self.chars.contains(&char_id) {
+                        self.chars
+                            .insert_after(origin, c, char_id.nid, char_id.ts)?;
+                        let index = self.utf16_index(self.chars.index_of(&char_id).unwrap());
+                        match changes.last_mut() {
+                            Some(TextChange::Insert {
+                                index: last_index,
+                                text,
+                            }) if *last_index + text.encode_utf16().count() == index => {
+                                text.push(c)
+                            }
+                            _ => changes.push(TextChange::Insert {
+                                index,
+                                text: String::from(c),
+                            }),
+                        }
+                    }
+                    self.last_ts = max(self.last_ts, ts);
+                    origin = Some(char_id);
+                    ts.increase_counter();
+                }
+            }
+            TextOp::Delete { ids } => {
+                for id in ids {
+                    if let Some(char_index) = self.chars.index_of(id) {
+                        let index = self.utf16_index(char_index);
+                        let len = self.chars.get(char_index).unwrap().len_utf16();
+                        match changes.last_mut() {
+                            Some(TextChange::Delete {
+                                index: last_index,
+                                len: last_len,
+                            }) if *last_index == index => *last_len += len,
+                            _ => changes.push(TextChange::Delete { index, len }),
+                        }
+                    }
+                    self.chars.delete(*id)?;
+                }
+            }
+        }
+        Ok(changes)
+    }
+
+    /// ### Merge
+    ///
+    /// Merges the state of another text into the current one.
+    ///
+    /// * Throws [`TextError::UnknownElement`] if the other state references an unknown character,
+    ///   in which case the text is left unchanged.
+    pub fn merge(&mut self, other: &Self) -> Result<(), TextError> {
+        self.chars.merge(&other.chars)?;
+        self.last_ts = max(self.last_ts, other.last_ts);
+        Ok(())
+    }
+
+    /// ### Character index
+    ///
+    /// Converts a position in UTF-16 code units to a character index.
+    fn char_index(&self, index: usize) -> Result<usize, TextError> {
+        let mut utf16_index = 0;
+        for (char_index, c) in self.chars.iter().enumerate() {
+            if utf16_index == index {
+                return Ok(char_index);
+            } else if utf16_index > index {
+                return Err(TextError::InvalidIndex);
+            }
+            utf16_index += c.len_utf16();
+        }
+        if utf16_index == index {
+            Ok(self.chars.len())
+        } else {
+            Err(TextError::InvalidIndex)
+        }
+    }
+
+    /// ### UTF-16 index
+    ///
+    /// Converts a character index to a position in UTF-16 code units.
+    fn utf16_index(&self, char_index: usize) -> usize {
+        self.chars
+            .iter()
+            .take(char_index)
+            .map(|c| c.len_utf16())
+            .sum()
+    }
+}
+
+#[wasm_bindgen]
+impl Text {
+    /// ### New text (JS)
+    ///
+    /// Creates a new empty text.
+    #[wasm_bindgen(constructor)]
+    pub fn new_js() -> Text {
+        Self::default()
+    }
+
+    /// ### Insert (JS)
+    ///
+    /// Inserts a string at a given position (in UTF-16 code units) and returns the encoded
+    /// operation.
+    ///
+    /// #### Errors
+    ///
+    /// A JS exception is thrown if the position is not valid.
+    #[wasm_bindgen(js_name = insert)]
+    pub fn insert_js(&mut self, index: usize, text: &str, nid: UID, ts: Timestamp) -> Vec<u8> {
+        self.insert(index, text, nid, ts)
+            .unwrap_throw()
+            .serialize_var()
+    }
+
+    /// ### Delete (JS)
+    ///
+    /// Deletes a range (in UTF-16 code units) and returns the encoded operation.
+    ///
+    /// #### Errors
+    ///
+    /// A JS exception is thrown if the range is not valid.
+    #[wasm_bindgen(js_name = delete)]
+    pub fn delete_js(&mut self, index: usize, len: usize) -> Vec<u8> {
+        self.delete(index, len).unwrap_throw().serialize_var()
+    }
+
+    /// ### Apply operation (JS)
+    ///
+    /// Applies an encoded operation and returns the resulting changes as an array of objects
+    /// `{ kind: "insert", index, text }` or `{ kind: "delete", index, length }`.
+    ///
+    /// #### Errors
+    ///
+    /// A JS exception is thrown if the operation cannot be decoded or applied.
+    #[wasm_bindgen(js_name = applyOp)]
+    pub fn apply_js(&mut self, encoded: &[u8]) -> js_sys::Array {
+        let (op, _) = TextOp::deserialize_var(encoded).unwrap_throw();
+        self.apply(&op)
+            .unwrap_throw()
+            .iter()
+            .map(TextChange::to_js)
+            .collect()
+    }
+
+    /// ### To string
+    ///
+    /// Returns the current content of the text.
+    #[wasm_bindgen(js_name = toString)]
+    pub fn as_string(&self) -> String {
+        self.to_string()
+    }
+
+    /// ### Serialize
+    ///
+    /// Returns the state of the text in binary format.
+    #[wasm_bindgen(js_name = serialize)]
+    pub fn serialize_js(&self) -> Vec<u8> {
+        self.serialize_var()
+    }
+
+    /// ### Deserialize
+    ///
+    /// Constructs a text from an encoded version.
+    ///
+    /// #### Errors
+    ///
+    /// A JS exception is thrown if the encoded state is not valid.
+    #[wasm_bindgen(js_name = deserialize)]
+    pub fn deserialize_js(encoded: &[u8]) -> Text {
+        Text::deserialize_var(encoded).unwrap_throw().0
+    }
+}
+
+impl Display for Text {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.chars.iter().collect::<String>())
+    }
+}
+
+//#region Serialization
+impl VarSerialize for Text {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = self.last_ts.serialize_var();
+        encoded.extend(self.chars.serialize_var());
+        encoded
+    }
+}
+
+impl VarDeserialize for Text {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (last_ts, rest) = Timestamp::deserialize_var(encoded)?;
+        let (chars, rest) = RGA::deserialize_var(rest)?;
+        Ok((Self { chars, last_ts }, rest))
+    }
+}
+//#endregion
+
+//#region TextError
+/// ## Text error
+///
+/// Custom error related to text edits.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TextError {
+    InvalidIndex,
+    UnknownElement,
+}
+
+impl From<RGAError> for TextError {
+    fn from(err: RGAError) -> Self {
+        match err {
+            RGAError::IndexOutOfBounds => TextError::InvalidIndex,
+            RGAError::UnknownElement => TextError::UnknownElement,
+        }
+    }
+}
+//#endregion
+
+#[cfg(test)]
+mod text_tests {
+    use super::*;
+    use crate::serialization::test_var_serialization;
+    use crate::time::hlc::{HybridLogicalClock, SysTimeHLC};
+
+    #[test]
+    fn utf16_indices_work() {
+        let mut hlc = SysTimeHLC::default();
+        let nid = UID::new();
+        let mut text = Text::new();
+
+        text.insert(0, "a👋b", nid, hlc.generate_timestamp())
+            .unwrap();
+        assert_eq!(
+            text.len(),
+            4,
+            "Length should be given in UTF-16 code units."
+        );
+        assert_eq!(
+            text.insert(2, "x", nid, hlc.generate_timestamp()),
+            Err(TextError::InvalidIndex),
+            "Positions inside a surrogate pair should be rejected."
+        );
+
+        text.insert(3, "c", nid, hlc.generate_timestamp()).unwrap();
+        assert_eq!(text.to_string(), "a👋cb");
+        text.delete(1, 2).unwrap();
+        assert_eq!(text.to_string(), "acb");
+    }
+
+    #[test]
+    fn remote_ops_emit_changes() {
+        let mut hlc = SysTimeHLC::default();
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut text_a = Text::new();
+        let mut text_b = Text::new();
+
+        let op = text_a
+            .insert(0, "hello", nid_a, hlc.generate_timestamp())
+            .unwrap();
+        assert_eq!(
+            text_b.apply(&op).unwrap(),
+            vec![TextChange::Insert {
+                index: 0,
+                text: String::from("hello")
+            }]
+        );
+        assert!(
+            text_b.apply(&op).unwrap().is_empty(),
+            "Duplicates should have no effect."
+        );
+
+        let op = text_b.delete(1, 3).unwrap();
+        assert_eq!(
+            text_a.apply(&op).unwrap(),
+            vec![TextChange::Delete { index: 1, len: 3 }]
+        );
+        assert_eq!(text_a.to_string(), "ho");
+
+        let op = text_a
+            .insert(1, "👋", nid_a, hlc.generate_timestamp())
+            .unwrap();
+        assert_eq!(
+            text_b.apply(&op).unwrap(),
+            vec![TextChange::Insert {
+                index: 1,
+                text: String::from("👋")
+            }]
+        );
+        assert_eq!(text_a, text_b, "Replicas should converge.");
+    }
+
+    #[test]
+    fn ops_with_unknown_characters_have_no_effect() {
+        let mut hlc = SysTimeHLC::default();
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut text_a = Text::new();
+        let mut text_b = Text::new();
+        let op = text_a
+            .insert(0, "abc", nid_a, hlc.generate_timestamp())
+            .unwrap();
+        text_b.apply(&op).unwrap();
+        let unknown_id = Dot::new(UID::new(), hlc.generate_timestamp());
+
+        let mut ids = text_b.ids_in(0, 3).unwrap();
+        ids.insert(1, unknown_id);
+        let before = text_b.clone();
+        assert_eq!(
+            text_b.apply(&TextOp::Delete { ids }),
+            Err(TextError::UnknownElement)
+        );
+        let orphan = TextOp::Insert {
+            id: Dot::new(nid_b, hlc.generate_timestamp()),
+            origin: Some(unknown_id),
+            text: String::from("x"),
+        };
+        assert_eq!(text_b.apply(&orphan), Err(TextError::UnknownElement));
+        assert_eq!(
+            text_b, before,
+            "Rejected ops should leave the text unchanged."
+        );
+    }
+
+    #[test]
+    fn concurrent_edits_converge() {
+        let mut hlc = SysTimeHLC::default();
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut text_a = Text::new();
+        let op = text_a
+            .insert(0, "ac", nid_a, hlc.generate_timestamp())
+            .unwrap();
+        let mut text_b = Text::new();
+        text_b.apply(&op).unwrap();
+
+        let op_a = text_a
+            .insert(1, "b", nid_a, hlc.generate_timestamp())
+            .unwrap();
+        let op_b = text_b
+            .insert(2, "d", nid_b, hlc.generate_timestamp())
+            .unwrap();
+        text_a.apply(&op_b).unwrap();
+        text_b.apply(&op_a).unwrap();
+
+        assert_eq!(text_a.to_string(), "abcd");
+        assert_eq!(text_a.to_string(), text_b.to_string());
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let mut hlc = SysTimeHLC::default();
+        let nid = UID::new();
+        let mut text = Text::new();
+        let insert = text
+            .insert(0, "abc", nid, hlc.generate_timestamp())
+            .unwrap();
+        let delete = text.delete(0, 1).unwrap();
+
+        test_var_serialization(insert);
+        test_var_serialization(delete);
+        test_var_serialization(text);
+    }
+}
@@ -334,6 +334,8 @@ impl Deserialize<TS_SIZE> for Timestamp {
         Timestamp(u64::from_be_bytes(encoded))
     }
 }
+
+crate::impl_var_serialization!(Timestamp, TS_SIZE);
 //#endregion
 
 //#region TimestampError
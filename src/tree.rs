@@ -0,0 +1,547 @@
+//! # Tree CRDT
+//!
+//! Implementation of a movable tree CRDT, suitable for hierarchies such as folders and pages.
+//!
+//! Every node is identified by the [`Dot`] of the operation that created it and carries some
+//! metadata (e.g. a name). All updates are _move operations_ that set the parent and metadata of a
+//! node: creating a node moves it from nowhere, deleting a node moves it to the trash.
+//!
+//! Move operations are totally ordered by their IDs (HLC timestamp first, node ID second). Every
+//! replica keeps a log of the applied operations, sorted by ID. When an operation arrives that is
+//! older than some logged ones, those are undone, the new operation is applied and the undone
+//! ones are redone. So all replicas end up applying the same operations in the same order.
+//!
+//! A move that would make a node a descendant of itself is skipped. Since every replica skips
+//! the same moves, concurrent moves never create a cycle.
+//!
+//! Reference: [A highly-available move operation for replicated trees - Kleppmann et. al.](https://doi.org/10.1109/TPDS.2021.3118603)
+//!
+//! ### Usage
+//!
+//! ```rust
+//! use crdts::time::hlc::{HybridLogicalClock, SysTimeHLC};
+//! use crdts::tree::{Tree, TreeParent};
+//! use crdts::uid::UID;
+//!
+//! let mut hlc = SysTimeHLC::default();
+//! let nid = UID::new();
+//!
+//! let mut tree = Tree::new();
+//! let docs = tree.create(TreeParent::Root, "docs", nid, hlc.generate_timestamp()).unwrap();
+//! let notes = tree.create(TreeParent::Node(docs.node), "notes", nid, hlc.generate_timestamp()).unwrap();
+//!
+//! let mut replica = Tree::new();
+//! replica.apply(&notes);
+//! replica.apply(&docs);
+//! assert_eq!(replica.children(TreeParent::Node(docs.node)), vec![notes.node]);
+//! ```
+use crate::{
+    dot::Dot,
+    serialization::{DeserializeError, VarDeserialize, VarSerialize},
+    time::timestamp::Timestamp,
+    uid::UID,
+};
+use std::collections::BTreeMap;
+
+//#region Parent
+/// ## Tree parent
+///
+/// Parent of a node: the root of the tree, the trash (for deleted nodes) or another node.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum TreeParent {
+    Root,
+    Trash,
+    Node(Dot),
+}
+
+impl VarSerialize for TreeParent {
+    fn serialize_var(&self) -> Vec<u8> {
+        match self {
+            TreeParent::Root => vec![0u8],
+            TreeParent::Trash => vec![1u8],
+            TreeParent::Node(id) => {
+                let mut encoded = vec![2u8];
+                encoded.extend(id.serialize_var());
+                encoded
+            }
+        }
+    }
+}
+
+impl VarDeserialize for TreeParent {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (kind, rest) = u8::deserialize_var(encoded)?;
+        match kind {
+            0 => Ok((TreeParent::Root, rest)),
+            1 => Ok((TreeParent::Trash, rest)),
+            2 => Dot::deserialize_var(rest).map(|(id, rest)| (TreeParent::Node(id), rest)),
+            _ => Err(DeserializeError::InvalidValue),
+        }
+    }
+}
+//#endregion
+
+//#region Operations
+/// ## Tree move
+///
+/// Move operation generated by a local update that can be sent to other replicas.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TreeMove<T> {
+    /// ### ID
+    ///
+    /// Unique ID of the operation, which also determines its position in the log.
+    pub id: Dot,
+
+    /// ### Node
+    ///
+    /// ID of the moved node.
+    pub node: Dot,
+
+    /// ### Parent
+    ///
+    /// New parent of the node.
+    pub parent: TreeParent,
+
+    /// ### Metadata
+    ///
+    /// New metadata of the node.
+    pub meta: T,
+}
+
+impl<T: VarSerialize> VarSerialize for TreeMove<T> {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = self.id.serialize_var();
+        encoded.extend(self.node.serialize_var());
+        encoded.extend(self.parent.serialize_var());
+        encoded.extend(self.meta.serialize_var());
+        encoded
+    }
+}
+
+impl<T: VarDeserialize> VarDeserialize for TreeMove<T> {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (id, rest) = Dot::deserialize_var(encoded)?;
+        let (node, rest) = Dot::deserialize_var(rest)?;
+        let (parent, rest) = TreeParent::deserialize_var(rest)?;
+        let (meta, rest) = T::deserialize_var(rest)?;
+        Ok((
+            Self {
+                id,
+                node,
+                parent,
+                meta,
+            },
+            rest,
+        ))
+    }
+}
+
+/// ## Log entry
+///
+/// Applied move operation together with the previous parent and metadata of the node, so that
+/// the operation can be undone.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct LogEntry<T> {
+    op: TreeMove<T>,
+    old: Option<(TreeParent, T)>,
+}
+//#endregion
+
+/// ## Tree
+///
+/// Movable tree whose nodes carry metadata of a generic type.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Tree<T: Clone> {
+    /// ### Nodes
+    ///
+    /// Current parent and metadata of every node, including deleted ones.
+    nodes: BTreeMap<Dot, (TreeParent, T)>,
+
+    /// ### Log
+    ///
+    /// Applied operations, sorted by ascending ID.
+    log: Vec<LogEntry<T>>,
+}
+
+impl<T: Clone> Default for Tree<T> {
+    fn default() -> Self {
+        Self {
+            nodes: BTreeMap::new(),
+            log: Vec::new(),
+        }
+    }
+}
+
+impl<T: Clone> Tree<T> {
+    /// ### New tree
+    ///
+    /// Creates a new tree containing only the root.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ### Get
+    ///
+    /// Returns the metadata of a node, or `None` if the node does not exist or has been deleted.
+    pub fn get(&self, node: &Dot) -> Option<&T> {
+        match self.nodes.get(node) {
+            Some((parent, meta)) if !self.is_deleted_parent(*parent) => Some(meta),
+            _ => None,
+        }
+    }
+
+    /// ### Get parent
+    ///
+    /// Returns the parent of a node, or `None` if the node does not exist.
+    #[inline]
+    pub fn get_parent(&self, node: &Dot) -> Option<TreeParent> {
+        self.nodes.get(node).map(|(parent, _)| *parent)
+    }
+
+    /// ### Children
+    ///
+    /// Returns the IDs of the children of a node (or of the root, or of the trash), sorted by ID.
+    pub fn children(&self, parent: TreeParent) -> Vec<Dot> {
+        self.nodes
+            .iter()
+            .filter(|(_, (node_parent, _))| *node_parent == parent)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// ### Is deleted
+    ///
+    /// Returns whether a node is in the trash, directly or through one of its ancestors.
+    #[inline]
+    pub fn is_deleted(&self, node: &Dot) -> bool {
+        self.is_deleted_parent(TreeParent::Node(*node))
+    }
+
+    /// ### Log
+    ///
+    /// Returns the applied operations, sorted by ascending ID.
+    pub fn log(&self) -> impl Iterator<Item = &TreeMove<T>> {
+        self.log.iter().map(|entry| &entry.op)
+    }
+
+    /// ### Create
+    ///
+    /// Creates a new node under a parent. The ID of the node is the ID of the operation.
+    ///
+    /// * `parent` - Parent of the new node.
+    /// * `meta` - Metadata of the new node.
+    /// * `nid` - ID of the current node.
+    /// * `ts` - Timestamp of the operation.
+    /// * Throws [`TreeError::UnknownNode`] if the parent does not exist.
+    pub fn create(
+        &mut self,
+        parent: TreeParent,
+        meta: T,
+        nid: UID,
+        ts: Timestamp,
+    ) -> Result<TreeMove<T>, TreeError> {
+        self.check_parent(parent)?;
+        let id = Dot::new(nid, ts);
+        let op = TreeMove {
+            id,
+            node: id,
+            parent,
+            meta,
+        };
+        self.apply(&op);
+        Ok(op)
+    }
+
+    /// ### Move node
+    ///
+    /// Moves a node under a new parent, keeping its metadata.
+    ///
+    /// * `node` - ID of the node to move.
+    /// * `parent` - New parent of the node.
+    /// * Throws [`TreeError::UnknownNode`] if the node or the parent do not exist.
+    /// * Throws [`TreeError::Cycle`] if the parent is the node itself or one of its descendants.
+    pub fn move_node(
+        &mut self,
+        node: Dot,
+        parent: TreeParent,
+        nid: UID,
+        ts: Timestamp,
+    ) -> Result<TreeMove<T>, TreeError> {
+        let (_, meta) = self.nodes.get(&node).ok_or(TreeError::UnknownNode)?;
+        self.check_parent(parent)?;
+        if self.is_ancestor(node, parent) {
+            return Err(TreeError::Cycle);
+        }
+        let op = TreeMove {
+            id: Dot::new(nid, ts),
+            node,
+            parent,
+            meta: meta.clone(),
+        };
+        self.apply(&op);
+        Ok(op)
+    }
+
+    /// ### Delete
+    ///
+    /// Deletes a node (and with it all its descendants) by moving it to the trash.
+    ///
+    /// * Throws [`TreeError::UnknownNode`] if the node does not exist.
+    #[inline]
+    pub fn delete(&mut self, node: Dot, nid: UID, ts: Timestamp) -> Result<TreeMove<T>, TreeError> {
+        self.move_node(node, TreeParent::Trash, nid, ts)
+    }
+
+    /// ### Apply
+    ///
+    /// Applies a move operation (usually received from another replica). Logged operations with a
+    /// larger ID are undone and redone after it, so that the result does not depend on the order
+    /// in which operations are received.
+    ///
+    /// * Operations that have already been applied are ignored.
+    pub fn apply(&mut self, op: &TreeMove<T>) {
+        let position = self.log.partition_point(|entry| entry.op.id < op.id);
+        if self
+            .log
+            .get(position)
+            .is_some_and(|entry| entry.op.id == op.id)
+        {
+            return;
+        }
+
+        let undone = self.log.split_off(position);
+        for entry in undone.iter().rev() {
+            self.undo(entry);
+        }
+        self.do_op(op.clone());
+        for entry in undone {
+            self.do_op(entry.op);
+        }
+    }
+
+    /// ### Merge
+    ///
+    /// Merges another tree into the current one by applying the operations of its log.
+    pub fn merge(&mut self, other: &Self) {
+        for entry in &other.log {
+            self.apply(&entry.op);
+        }
+    }
+
+    /// ### Do operation
+    ///
+    /// Applies an operation at the end of the log. Moves that would create a cycle are logged
+    /// without effect.
+    fn do_op(&mut self, op: TreeMove<T>) {
+        let old = self.nodes.get(&op.node).cloned();
+        if !self.is_ancestor(op.node, op.parent) {
+            self.nodes.insert(op.node, (op.parent, op.meta.clone()));
+        }
+        self.log.push(LogEntry { op, old });
+    }
+
+    /// ### Undo operation
+    ///
+    /// Restores the state of the moved node before an operation.
+    fn undo(&mut self, entry: &LogEntry<T>) {
+        match &entry.old {
+            Some(old) => {
+                self.nodes.insert(entry.op.node, old.clone());
+            }
+            None => {
+                self.nodes.remove(&entry.op.node);
+            }
+        }
+    }
+
+    /// ### Is ancestor
+    ///
+    /// Returns whether `ancestor` is `parent` itself or one of its ancestors.
+    fn is_ancestor(&self, ancestor: Dot, mut parent: TreeParent) -> bool {
+        while let TreeParent::Node(id) = parent {
+            if id == ancestor {
+                return true;
+            }
+            parent = match self.nodes.get(&id) {
+                Some((next, _)) => *next,
+                None => return false,
+            };
+        }
+        false
+    }
+
+    /// ### Is deleted parent
+    ///
+    /// Returns whether a parent is the trash or a node in the trash.
+    fn is_deleted_parent(&self, mut parent: TreeParent) -> bool {
+        loop {
+            match parent {
+                TreeParent::Root => return false,
+                TreeParent::Trash => return true,
+                TreeParent::Node(id) => match self.nodes.get(&id) {
+                    Some((next, _)) => parent = *next,
+                    None => return false,
+                },
+            }
+        }
+    }
+
+    /// ### Check parent
+    ///
+    /// Throws [`TreeError::UnknownNode`] if a parent node does not exist.
+    fn check_parent(&self, parent: TreeParent) -> Result<(), TreeError> {
+        match parent {
+            TreeParent::Node(id) if !self.nodes.contains_key(&id) => Err(TreeError::UnknownNode),
+            _ => Ok(()),
+        }
+    }
+}
+
+//#region Serialization
+impl<T: Clone + VarSerialize> VarSerialize for Tree<T> {
+    fn serialize_var(&self) -> Vec<u8> {
+        self.log().cloned().collect::<Vec<_>>().serialize_var()
+    }
+}
+
+impl<T: Clone + VarDeserialize> VarDeserialize for Tree<T> {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (ops, rest) = Vec::<TreeMove<T>>::deserialize_var(encoded)?;
+        let mut tree = Self::new();
+        for op in &ops {
+            tree.apply(op);
+        }
+        Ok((tree, rest))
+    }
+}
+//#endregion
+
+//#region TreeError
+/// ## Tree error
+///
+/// Custom error related to tree operations.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TreeError {
+    UnknownNode,
+    Cycle,
+}
+//#endregion
+
+#[cfg(test)]
+mod tree_tests {
+    use super::*;
+    use crate::serialization::test_var_serialization;
+    use crate::time::hlc::{HybridLogicalClock, SysTimeHLC};
+
+    #[test]
+    fn create_move_and_delete_work() {
+        let mut hlc = SysTimeHLC::default();
+        let nid = UID::new();
+        let mut tree = Tree::new();
+
+        let a = tree
+            .create(TreeParent::Root, 'a', nid, hlc.generate_timestamp())
+            .unwrap()
+            .node;
+        let b = tree
+            .create(TreeParent::Root, 'b', nid, hlc.generate_timestamp())
+            .unwrap()
+            .node;
+        assert_eq!(tree.children(TreeParent::Root), vec![a, b]);
+
+        tree.move_node(b, TreeParent::Node(a), nid, hlc.generate_timestamp())
+            .unwrap();
+        assert_eq!(tree.children(TreeParent::Root), vec![a]);
+        assert_eq!(tree.children(TreeParent::Node(a)), vec![b]);
+        assert_eq!(
+            tree.move_node(a, TreeParent::Node(b), nid, hlc.generate_timestamp()),
+            Err(TreeError::Cycle)
+        );
+
+        tree.delete(a, nid, hlc.generate_timestamp()).unwrap();
+        assert!(
+            tree.is_deleted(&b),
+            "Descendants of deleted nodes are deleted."
+        );
+        assert_eq!(tree.get(&b), None);
+        assert_eq!(tree.children(TreeParent::Trash), vec![a]);
+    }
+
+    #[test]
+    fn concurrent_moves_do_not_create_cycles() {
+        let mut hlc = SysTimeHLC::default();
+        let (nid_a, nid_b) = (UID::new(), UID::new());
+        let mut tree_a = Tree::new();
+        let a = tree_a
+            .create(TreeParent::Root, 'a', nid_a, hlc.generate_timestamp())
+            .unwrap()
+            .node;
+        let b = tree_a
+            .create(TreeParent::Root, 'b', nid_a, hlc.generate_timestamp())
+            .unwrap()
+            .node;
+        let mut tree_b = tree_a.clone();
+
+        tree_a
+            .move_node(a, TreeParent::Node(b), nid_a, hlc.generate_timestamp())
+            .unwrap();
+        tree_b
+            .move_node(b, TreeParent::Node(a), nid_b, hlc.generate_timestamp())
+            .unwrap();
+
+        let mut merged_a = tree_a.clone();
+        merged_a.merge(&tree_b);
+        tree_b.merge(&tree_a);
+
+        assert_eq!(merged_a, tree_b, "Replicas should converge.");
+        assert_eq!(merged_a.children(TreeParent::Root), vec![b]);
+        assert_eq!(merged_a.children(TreeParent::Node(b)), vec![a]);
+    }
+
+    #[test]
+    fn out_of_order_operations_converge() {
+        let mut hlc = SysTimeHLC::default();
+        let nid = UID::new();
+        let mut tree = Tree::new();
+        let ops = vec![
+            tree.create(TreeParent::Root, 'a', nid, hlc.generate_timestamp())
+                .unwrap(),
+            tree.create(TreeParent::Root, 'b', nid, hlc.generate_timestamp())
+                .unwrap(),
+        ];
+        let (a, b) = (ops[0].node, ops[1].node);
+        let ops = [
+            ops,
+            vec![
+                tree.move_node(b, TreeParent::Node(a), nid, hlc.generate_timestamp())
+                    .unwrap(),
+                tree.delete(b, nid, hlc.generate_timestamp()).unwrap(),
+            ],
+        ]
+        .concat();
+
+        let mut replica = Tree::new();
+        for op in ops.iter().rev() {
+            replica.apply(op);
+        }
+        replica.apply(&ops[0]);
+        assert_eq!(replica, tree);
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let mut hlc = SysTimeHLC::default();
+        let nid = UID::new();
+        let mut tree = Tree::new();
+        let op = tree
+            .create(TreeParent::Root, 1u8, nid, hlc.generate_timestamp())
+            .unwrap();
+        tree.create(TreeParent::Node(op.node), 2, nid, hlc.generate_timestamp())
+            .unwrap();
+        tree.delete(op.node, nid, hlc.generate_timestamp()).unwrap();
+
+        test_var_serialization(op);
+        test_var_serialization(tree);
+    }
+}
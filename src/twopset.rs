@@ -0,0 +1,175 @@
+//! # 2P-Set CRDT
+//!
+//! Implementation of a _two-phase set (2P-Set)_ CRDT, made of two [`GSet`]s: one for the added
+//! elements and one for the removed elements (tombstones).
+//!
+//! An element is in the set if it has been added and not removed. Removal is permanent: once an
+//! element has been removed, it can never be added again.
+//!
+//! ### Usage
+//!
+//! ```rust
+//! use crdts::twopset::{TwoPSet, TwoPSetError};
+//!
+//! let mut set = TwoPSet::new();
+//! set.insert(1u8).unwrap();
+//! set.remove(&1).unwrap();
+//!
+//! assert!(!set.contains(&1));
+//! assert_eq!(set.insert(1), Err(TwoPSetError::Removed), "Removal is permanent.");
+//! ```
+use crate::{
+    gset::GSet,
+    serialization::{DeserializeError, VarDeserialize, VarSerialize},
+};
+
+/// ## 2P-Set
+///
+/// Two-phase set over a generic ordered type.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TwoPSet<T: Ord + Clone> {
+    /// ### Added
+    ///
+    /// Elements that have been added, including removed ones.
+    added: GSet<T>,
+
+    /// ### Removed
+    ///
+    /// Elements that have been removed.
+    removed: GSet<T>,
+}
+
+impl<T: Ord + Clone> Default for TwoPSet<T> {
+    fn default() -> Self {
+        Self {
+            added: GSet::new(),
+            removed: GSet::new(),
+        }
+    }
+}
+
+impl<T: Ord + Clone> TwoPSet<T> {
+    /// ### New 2P-Set
+    ///
+    /// Creates a new empty set.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ### Insert
+    ///
+    /// Adds an element to the set.
+    ///
+    /// * Throws [`TwoPSetError::Removed`] if the element has already been removed.
+    pub fn insert(&mut self, value: T) -> Result<(), TwoPSetError> {
+        if self.removed.contains(&value) {
+            return Err(TwoPSetError::Removed);
+        }
+        self.added.insert(value);
+        Ok(())
+    }
+
+    /// ### Remove
+    ///
+    /// Removes an element from the set, permanently.
+    ///
+    /// * Throws [`TwoPSetError::NotFound`] if the element is not in the set.
+    pub fn remove(&mut self, value: &T) -> Result<(), TwoPSetError> {
+        if !self.contains(value) {
+            return Err(TwoPSetError::NotFound);
+        }
+        self.removed.insert(value.clone());
+        Ok(())
+    }
+
+    /// ### Contains
+    ///
+    /// Returns whether an element is in the set.
+    #[inline]
+    pub fn contains(&self, value: &T) -> bool {
+        self.added.contains(value) && !self.removed.contains(value)
+    }
+
+    /// ### Iterate
+    ///
+    /// Returns an iterator over the elements of the set, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.added
+            .iter()
+            .filter(|value| !self.removed.contains(value))
+    }
+
+    /// ### Merge
+    ///
+    /// Merges another set into the current one.
+    ///
+    /// * `other` - State of another set.
+    pub fn merge(&mut self, other: &Self) {
+        self.added.merge(&other.added);
+        self.removed.merge(&other.removed);
+    }
+}
+
+//#region Serialization
+impl<T: Ord + Clone + VarSerialize> VarSerialize for TwoPSet<T> {
+    fn serialize_var(&self) -> Vec<u8> {
+        let mut encoded = self.added.serialize_var();
+        encoded.extend(self.removed.serialize_var());
+        encoded
+    }
+}
+
+impl<T: Ord + Clone + VarDeserialize> VarDeserialize for TwoPSet<T> {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (added, rest) = GSet::deserialize_var(encoded)?;
+        let (removed, rest) = GSet::deserialize_var(rest)?;
+        Ok((Self { added, removed }, rest))
+    }
+}
+//#endregion
+
+//#region TwoPSetError
+/// ## 2P-Set error
+///
+/// Custom error related to 2P-Set operations.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TwoPSetError {
+    Removed,
+    NotFound,
+}
+//#endregion
+
+#[cfg(test)]
+mod twopset_tests {
+    use super::*;
+    use crate::serialization::test_var_serialization;
+
+    #[test]
+    fn removal_wins_on_merge() {
+        let mut set_a = TwoPSet::new();
+        set_a.insert(1u8).unwrap();
+        let mut set_b = set_a.clone();
+
+        set_a.remove(&1).unwrap();
+        set_b.insert(2).unwrap();
+        assert_eq!(set_b.remove(&3), Err(TwoPSetError::NotFound));
+
+        let mut merged_b = set_b.clone();
+        merged_b.merge(&set_a);
+        set_a.merge(&set_b);
+
+        assert_eq!(set_a.iter().copied().collect::<Vec<_>>(), vec![2]);
+        assert_eq!(set_a, merged_b, "Merge should be commutative.");
+        assert_eq!(set_a.insert(1), Err(TwoPSetError::Removed));
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let mut set = TwoPSet::new();
+        set.insert(1u32).unwrap();
+        set.insert(2).unwrap();
+        set.remove(&1).unwrap();
+        test_var_serialization(set);
+    }
+}
@@ -279,6 +279,8 @@ impl Deserialize<UID_SIZE> for UID {
         UID(u128::from_be_bytes(encoded))
     }
 }
+
+crate::impl_var_serialization!(UID, UID_SIZE);
 //#endregion
 
 #[cfg(test)]
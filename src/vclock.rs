@@ -1,26 +1,122 @@
-use crate::gcounter::GCounter;
+use crate::{
+    gcounter::GCounter,
+    serialization::{DeserializeError, VarDeserialize, VarSerialize},
+    uid::UID,
+};
+use std::cmp::min;
 use std::cmp::Ordering;
 use std::cmp::Ordering::{Equal, Greater, Less};
 
+/// ## Causal dot
+///
+/// Identifier of a single event: the ID of the node and the value of its clock entry after the
+/// event.
+///
+/// * Unlike [`Dot`](crate::dot::Dot), which tags an operation with its HLC timestamp, a causal
+///   dot is numbered by the clock of the node, so the dots of a node form a contiguous range.
+pub type CausalDot = (UID, u64);
+
 /// ## VClock
 ///
 /// Implementation of a logical vector clock.
 ///
 /// * Implemented as a tuple struct over GCounter using the `newtype` pattern.
 /// * Implements the `PartialOrd` trait which allows for comparison of VClock timestamps.
+/// * Entries with a count of 0 are never stored, as they are read as 0 anyway. This keeps equal
+///   clocks equal under `PartialEq`, however they were built or decoded.
 #[derive(PartialEq, Clone, Debug)]
 pub struct VClock(GCounter);
 
 impl VClock {
-    pub fn new(node_id: Option<&String>) -> Self {
-        Self(GCounter::new(node_id))
+    /// ### New VClock
+    ///
+    /// Creates a new clock where every entry is 0.
+    ///
+    /// * `node_id` - ID of the current node. It gets no explicit entry until its first event.
+    pub fn new(node_id: Option<UID>) -> Self {
+        let mut counter = GCounter::new(node_id);
+        counter.state.clear();
+        Self(counter)
     }
-    pub fn increment(&mut self, node_id: &String) -> () {
+    pub fn increment(&mut self, node_id: UID) -> () {
         self.0.increment(node_id);
     }
     pub fn merge(&mut self, other: &Self) -> () {
         self.0.merge_from_state(&other.0);
     }
+
+    /// ### Get
+    ///
+    /// Returns the entry of a node, or 0 if the clock has none.
+    pub fn get(&self, node_id: UID) -> u64 {
+        self.0.state.get(&node_id).copied().unwrap_or(0)
+    }
+
+    /// ### Dot
+    ///
+    /// Returns the dot of the latest event of a node, e.g. the local one after incrementing the
+    /// clock.
+    #[inline]
+    pub fn dot(&self, node_id: UID) -> CausalDot {
+        (node_id, self.get(node_id))
+    }
+
+    /// ### Happened before
+    ///
+    /// Returns whether the clock is strictly smaller than another one, i.e. the events it
+    /// captures causally precede the ones of the other clock.
+    #[inline]
+    pub fn happened_before(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Less)
+    }
+
+    /// ### Is concurrent
+    ///
+    /// Returns whether neither of the clocks is smaller than or equal to the other.
+    #[inline]
+    pub fn is_concurrent(&self, other: &Self) -> bool {
+        self.partial_cmp(other).is_none()
+    }
+
+    /// ### Dominates
+    ///
+    /// Returns whether every entry of the clock is greater than or equal to the entry of another
+    /// one, i.e. the clock has seen all the events of the other clock.
+    pub fn dominates(&self, other: &Self) -> bool {
+        matches!(self.partial_cmp(other), Some(Greater | Equal))
+    }
+
+    /// ### Join
+    ///
+    /// Returns the least clock that dominates both clocks (entry-wise maximum).
+    pub fn join(&self, other: &Self) -> Self {
+        let mut joined = self.clone();
+        joined.merge(other);
+        joined
+    }
+
+    /// ### Meet
+    ///
+    /// Returns the greatest clock dominated by both clocks (entry-wise minimum), i.e. the events
+    /// both clocks have seen.
+    pub fn meet(&self, other: &Self) -> Self {
+        let state = self
+            .0
+            .state
+            .iter()
+            .map(|(node_id, count)| (*node_id, min(*count, other.get(*node_id))))
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        Self(GCounter { state })
+    }
+}
+
+impl From<GCounter> for VClock {
+    /// Uses the node counts of a counter as clock entries.
+    fn from(mut counter: GCounter) -> Self {
+        counter.state.retain(|_, count| *count > 0);
+        Self(counter)
+    }
 }
 
 impl PartialOrd for VClock {
@@ -47,7 +143,7 @@ impl PartialOrd for VClock {
                 }
             }
 
-            if partial_result == result {
+            if partial_result == Equal || partial_result == result {
                 continue;
             } else if result == Equal {
                 result = partial_result;
@@ -77,39 +173,204 @@ impl PartialOrd for VClock {
     }
 }
 
+//#region Serialization
+/// Clocks use the compact encoding of [`GCounter`]: a varint number of entries followed by the
+/// node IDs and their varint counts.
+impl VarSerialize for VClock {
+    fn serialize_var(&self) -> Vec<u8> {
+        self.0.serialize_var()
+    }
+}
+
+impl VarDeserialize for VClock {
+    fn deserialize_var(encoded: &[u8]) -> Result<(Self, &[u8]), DeserializeError> {
+        let (counter, rest) = GCounter::deserialize_var(encoded)?;
+        Ok((Self::from(counter), rest))
+    }
+}
+
+impl VClock {
+    /// ### Serialize delta
+    ///
+    /// Encodes only the entries that differ from a base clock the receiver already knows, with the
+    /// same format as the full clock. Missing entries are read as 0.
+    ///
+    /// * `base` - Clock known by the receiver.
+    pub fn serialize_delta(&self, base: &Self) -> Vec<u8> {
+        let nodes = self.0.state.keys().chain(base.0.state.keys());
+        let state = nodes
+            .filter(|node_id| self.get(**node_id) != base.get(**node_id))
+            .map(|node_id| (*node_id, self.get(*node_id)))
+            .collect();
+        GCounter { state }.serialize_var()
+    }
+
+    /// ### Deserialize delta
+    ///
+    /// Decodes a clock encoded with [`VClock::serialize_delta`] against the same base clock.
+    ///
+    /// * `encoded` - Encoded delta.
+    /// * `base` - Clock the delta was computed against.
+    /// * Returns the decoded clock and the rest of the buffer.
+    pub fn deserialize_delta<'a>(
+        encoded: &'a [u8],
+        base: &Self,
+    ) -> Result<(Self, &'a [u8]), DeserializeError> {
+        let (delta, rest) = GCounter::deserialize_var(encoded)?;
+        let mut clock = base.clone();
+        for (node_id, count) in delta.state {
+            if count == 0 {
+                clock.0.state.remove(&node_id);
+            } else {
+                clock.0.state.insert(node_id, count);
+            }
+        }
+        Ok((clock, rest))
+    }
+}
+//#endregion
+
 #[cfg(test)]
 mod vclock_tests {
     use super::*;
+    use crate::serialization::test_var_serialization;
 
     #[test]
     fn comparison_works() {
-        let mut vclock_a = VClock::new(Some(&String::from("a")));
-        let mut vclock_b = VClock::new(Some(&String::from("b")));
+        let (a, b) = (UID::new(), UID::new());
+        let mut vclock_a = VClock::new(Some(a));
+        let mut vclock_b = VClock::new(Some(b));
         // vclock_a: { a: 0 }, vclock_b: { b: 0 }. Should be EQUAL.
         assert_eq!(vclock_a.partial_cmp(&vclock_b), Some(Equal));
 
-        vclock_a.increment(&String::from("a"));
+        vclock_a.increment(a);
         // vclock_a: { a: 1 }, vclock_b: { b: 0 }. Should be GREATER.
         assert_eq!(vclock_a.partial_cmp(&vclock_b), Some(Greater));
 
-        vclock_b.increment(&String::from("a"));
+        vclock_b.increment(a);
         // vclock_a: { a: 1 }, vclock_b: { a: 1, b: 0 }. Should be EQUAL.
         assert_eq!(vclock_a.partial_cmp(&vclock_b), Some(Equal));
 
-        vclock_a.increment(&String::from("b"));
+        vclock_a.increment(b);
         // vclock_a: { a: 1, b: 1 }, vclock_b: { a: 1, b: 0 }. Should be GREATER.
         assert_eq!(vclock_a.partial_cmp(&vclock_b), Some(Greater));
 
-        vclock_b.increment(&String::from("b"));
+        vclock_b.increment(b);
         // vclock_a: { a: 1, b: 1 }, vclock_b: { a: 1, b: 1 }. Should be EQUAL.
         assert_eq!(vclock_a.partial_cmp(&vclock_b), Some(Equal));
 
-        vclock_b.increment(&String::from("b"));
+        vclock_b.increment(b);
         // vclock_a: { a: 1, b: 1 }, vclock_b: { a: 1, b: 2 }. Should be LESS.
         assert_eq!(vclock_a.partial_cmp(&vclock_b), Some(Less));
 
-        vclock_a.increment(&String::from("a"));
+        vclock_a.increment(a);
         // vclock_a: { a: 2, b: 1 }, vclock_b: { a: 1, b: 2 }. Should be CONCURRENT.
         assert_eq!(vclock_a.partial_cmp(&vclock_b), None);
     }
+
+    #[test]
+    fn comparison_skips_equal_entries() {
+        let (a, b) = (UID::new(), UID::new());
+        let mut vclock_a = VClock::new(None);
+        let mut vclock_b = VClock::new(None);
+        vclock_a.increment(b);
+        vclock_b.increment(a);
+        vclock_b.increment(b);
+        // vclock_a: { b: 1 }, vclock_b: { a: 1, b: 1 }. Should be LESS.
+        assert_eq!(vclock_a.partial_cmp(&vclock_b), Some(Less));
+        assert_eq!(vclock_b.partial_cmp(&vclock_a), Some(Greater));
+    }
+
+    #[test]
+    fn causal_relations_work() {
+        let (a, b) = (UID::new(), UID::new());
+        let mut vclock_a = VClock::new(None);
+        vclock_a.increment(a);
+        let mut vclock_b = vclock_a.clone();
+        vclock_b.increment(b);
+        assert_eq!(vclock_b.dot(b), (b, 1));
+
+        assert!(vclock_a.happened_before(&vclock_b));
+        assert!(vclock_b.dominates(&vclock_a) && vclock_a.dominates(&vclock_a));
+        assert!(!vclock_a.happened_before(&vclock_a));
+
+        vclock_a.increment(a);
+        // vclock_a: { a: 2 }, vclock_b: { a: 1, b: 1 }.
+        assert!(vclock_a.is_concurrent(&vclock_b));
+        assert!(!vclock_a.dominates(&vclock_b) && !vclock_b.dominates(&vclock_a));
+
+        let joined = vclock_a.join(&vclock_b);
+        assert_eq!((joined.get(a), joined.get(b)), (2, 1));
+        assert!(joined.dominates(&vclock_a) && joined.dominates(&vclock_b));
+
+        let met = vclock_a.meet(&vclock_b);
+        assert_eq!((met.get(a), met.get(b)), (1, 0));
+        assert!(met.happened_before(&vclock_a) && met.happened_before(&vclock_b));
+    }
+
+    #[test]
+    fn serialization_deserialization_works() {
+        let (a, b) = (UID::new(), UID::new());
+        let mut vclock = VClock::new(Some(a));
+        vclock.increment(b);
+        assert_eq!(vclock.get(b), 1);
+        assert_eq!(vclock.get(UID::new()), 0);
+        assert_eq!(
+            vclock.serialize_var().len(),
+            1 + (16 + 1),
+            "Zero entries are not stored."
+        );
+        test_var_serialization(vclock);
+
+        let mut counter = GCounter::new(Some(a));
+        counter.increment(b);
+        let mut expected = VClock::new(None);
+        expected.increment(b);
+        assert_eq!(
+            VClock::deserialize_var(&counter.serialize_var()),
+            Ok((expected, &[][..])),
+            "Decoded zero entries are dropped."
+        );
+    }
+
+    #[test]
+    fn delta_serialization_works() {
+        let (a, b, c) = (UID::new(), UID::new(), UID::new());
+        let mut base = VClock::new(None);
+        base.increment(a);
+        base.increment(b);
+        let mut vclock = base.clone();
+        for _ in 0..200 {
+            vclock.increment(a);
+        }
+        vclock.increment(c);
+
+        let encoded = vclock.serialize_delta(&base);
+        assert_eq!(
+            encoded.len(),
+            1 + (16 + 2) + (16 + 1),
+            "Entry b is skipped."
+        );
+        assert_eq!(
+            VClock::deserialize_delta(&encoded, &base),
+            Ok((vclock.clone(), &[][..]))
+        );
+
+        let encoded = base.serialize_delta(&vclock);
+        let (decoded, _) = VClock::deserialize_delta(&encoded, &vclock).unwrap();
+        assert_eq!(decoded, base, "Deltas can also go back in time.");
+        assert_eq!(vclock.serialize_delta(&vclock), vec![0]);
+
+        let zero = VClock::new(Some(c));
+        let mut with_zero = VClock::new(Some(a));
+        with_zero.increment(b);
+        for (clock, base) in [(&zero, &base), (&base, &zero), (&with_zero, &zero)] {
+            let encoded = clock.serialize_delta(base);
+            assert_eq!(
+                VClock::deserialize_delta(&encoded, base),
+                Ok((clock.clone(), &[][..])),
+                "Zero entries should survive a round trip."
+            );
+        }
+    }
 }